@@ -1,322 +1,672 @@
-use std::{cell::RefCell, collections::HashSet, path::PathBuf, rc::Rc};
 use std::cmp::Ordering::Equal;
-use std::f32::consts::E;
-use std::ops::Deref;
+use std::collections::HashMap;
 
 use itertools::Itertools;
-use vecmath::{vec3_cross, vec3_dot};
-
-use crate::{edge::Edge, grid::Grid, point::Point, utils};
-use crate::utils::{calc_distance_points, calc_incircle_radius, calc_min_max_angle_of_triangle};
-
-struct BPA {
-    first_free_point_index: usize,
-    num_points_i_tried_to_seem_from: usize,
-    points: Rc<RefCell<Vec<Rc<RefCell<Point>>>>>,
+use vecmath::{vec3_add, vec3_dot, Vector3};
+
+use crate::{edge::{Edge, EdgeState}, error::BpaError, grid::Grid, point::{PointState, Points}, utils};
+use crate::geometry::{calc_ball_center, calc_oriented_normal, calc_triangle_normal, is_ball_empty, PivotCircle};
+use crate::mesh::Mesh;
+use crate::radius::estimate_spacing;
+use crate::reconstruct::{Progress, ReconstructionOptions};
+use crate::utils::calc_min_max_angle_of_triangle;
+
+/// Number of points whose nearest neighbor distance is measured to estimate missing radii.
+const NUM_SPACING_SAMPLES: usize = 1000;
+
+/// Indices in `Grid::edges` of the three edges of a seed triangle.
+type SeedTriangle = (u32, u32, u32);
+/// Triangles grown by a parallel worker, with the ball radius that produced them.
+type BlockTriangles = Vec<([u32; 3], f32)>;
+
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) struct BPA {
+    points: Points,
     radius: f32,
+    options: ReconstructionOptions,
     grid: Grid,
-    num_free_points: usize,
-    num_workers: usize,
+    /// Points that may be a vertex of new triangles. Workers of a parallel reconstruction
+    /// also see the points around their block, but do not own them.
+    is_owned: Vec<bool>,
 }
 
 impl BPA {
-    pub fn new(points: Vec<Rc<RefCell<Point>>>, radius: f32, num_workers: usize) -> BPA {
-        let rcpoints = Rc::new(RefCell::new(points));
-        let rcpointslen = rcpoints.borrow().len();
-        BPA {
-            first_free_point_index: 0,
-            num_points_i_tried_to_seem_from: 0,
-            points: rcpoints.clone(),
+    /// `options.radii` are the ball radii of the successive passes, they are used in
+    /// increasing order. Without radii, they are estimated from the point spacing.
+    ///
+    /// Every point needs finite coordinates and a normal, the orientation of the triangles
+    /// is checked against the normals.
+    pub fn new(points: Points, mut options: ReconstructionOptions) -> Result<BPA, BpaError> {
+        if points.len() < 3 {
+            return Err(BpaError::TooFewPoints { found: points.len(), required: 3 });
+        }
+
+        if let Some(point_id) = points.ids().find(|&p| points.coords(p).iter().any(|c| !c.is_finite())) {
+            return Err(BpaError::NonFiniteCoordinate { point_id });
+        }
+
+        if let Some(point_id) = points.ids().find(|&p| points.normal(p).is_none()) {
+            return Err(BpaError::MissingNormal { point_id });
+        }
+
+        if let Some(&radius) = options.radii.iter().find(|r| !r.is_finite() || **r <= 0.) {
+            return Err(BpaError::InvalidRadius(radius));
+        }
+
+        if options.radii.is_empty() {
+            let spacing = estimate_spacing(&points, NUM_SPACING_SAMPLES).ok_or(BpaError::DegenerateInput)?;
+            options.radii = spacing.suggested_radii();
+        }
+
+        options.radii.sort_by(f32::total_cmp);
+        options.radii.dedup();
+
+        let radius = options.radii[0];
+        let num_points = points.len();
+        let grid = Grid::new(radius, &points);
+
+        if grid.bounding_box_size == 0. {
+            return Err(BpaError::DegenerateInput);
+        }
+
+        Ok(BPA {
+            grid,
+            points,
             radius,
-            grid: Grid::new(radius, rcpoints.clone()),
-            num_free_points: rcpointslen,
-            num_workers,
+            options,
+            is_owned: vec![true; num_points],
+        })
+    }
+
+    /// Returns the reconstructed mesh, its vertices are all the points in their original order.
+    pub fn into_mesh(self) -> Mesh {
+        // Front points are only joined to triangles they share an edge with, so the fans of
+        // triangles around a point never meet at the point alone.
+        debug_assert!(self.points.ids().all(|p| self.grid.mesh.vertex_fans(p).len() <= 1), "pinched vertex in the mesh");
+        let points = self.points;
+
+        Mesh {
+            vertices: points.ids().map(|p| points.coords(p)).collect(),
+            normals: points.normals.iter().map(|n| n.unwrap_or_default()).collect(),
+            colors: points.colors,
+            topology: self.grid.mesh,
+            triangle_radii: self.grid.triangle_radii,
         }
     }
 
-    pub fn get_points_distances_from_edge(
-        points: Vec<Rc<RefCell<Point>>>,
-        p1: Rc<RefCell<Point>>,
-        p2: Rc<RefCell<Point>>,
-    ) -> Vec<f32> {
+    pub fn get_points_distances_from_edge(points: &Points, candidates: &[u32], p1: u32, p2: u32) -> Vec<f32> {
         // TODO add round to 2 digits
-        let dists_p1 = points
-            .iter()
-            .map(|p3| utils::calc_distance_points(p1.clone(), p3.clone()))
-            .collect::<Vec<_>>();
-        let dists_p2 = points
+        candidates
             .iter()
-            .map(|p3| utils::calc_distance_points(p2.clone(), p3.clone()))
-            .collect::<Vec<_>>();
-        let dists = dists_p1
-            .iter()
-            .zip(dists_p2)
-            .map(|(&a, b)| a + b)
-            .collect_vec();
-        dists
+            .map(|&p3| utils::calc_distance_points(points, p1, p3) + utils::calc_distance_points(points, p2, p3))
+            .collect_vec()
     }
 
-    pub fn get_third_point_of_triangle(
-        triangle_edges: Vec<Rc<RefCell<Edge>>>,
-        p1: Rc<RefCell<Point>>,
-        p2: Rc<RefCell<Point>>,
-    ) -> Option<Rc<RefCell<Point>>> {
-        let mut triangle_points = vec![];
-
-        for triangel_edge in triangle_edges.iter() {
-            // let first_point = triangel_edge.borrow().p1;
-            // let second_point = triangel_edge.borrow().p2;
-            triangle_points.push(triangel_edge.borrow().p1.clone());
-            triangle_points.push(triangel_edge.borrow().p2.clone());
+    /// Grows the mesh with one pass per radius. Each pass finds a seed triangle, pivots the
+    /// ball around every edge of the active front until the front is empty, then looks for
+    /// the next seed. Passes after the first one rebuild the grid for their radius and start
+    /// by pivoting around the boundary edges left by the previous pass.
+    ///
+    /// With more than one worker in the options, each pass first grows blocks of the cloud in
+    /// parallel with its radius (see [`BPA::grow_blocks_in_parallel`]), then stitches them.
+    ///
+    /// `limit_iterations` caps the number of seed triangles grown over all passes, `None` runs
+    /// until no seed can be found anymore. With a limit, the passes run on a single thread so
+    /// that it also holds over the workers. Triangles and edges are stored in `self.grid`.
+    ///
+    /// `progress` is called at the start of each pass and after each seed triangle is grown.
+    ///
+    /// Fails with [`BpaError::NoSeedFound`] if not a single triangle could be built.
+    pub fn create_mesh(
+        &mut self,
+        limit_iterations: Option<usize>,
+        first_point_index: usize,
+        progress: &mut dyn FnMut(&Progress),
+    ) -> Result<(), BpaError> {
+        let mut tried_to_expand_counter = 0;
+        let num_passes = self.options.radii.len();
+
+        for pass in 0..num_passes {
+            let mut report = |bpa: &BPA, num_points_scanned: usize| {
+                progress(&Progress {
+                    pass,
+                    num_passes,
+                    radius: bpa.radius,
+                    num_points_scanned,
+                    num_points: bpa.points.len(),
+                    num_triangles: bpa.grid.mesh.num_faces(),
+                })
+            };
+            if self.radius != self.options.radii[pass] {
+                self.radius = self.options.radii[pass];
+                self.grid.set_radius(self.radius, &self.points);
+            }
+
+            report(self, 0);
+
+            if self.options.num_workers > 1 && limit_iterations.is_none() {
+                self.grow_blocks_in_parallel()?;
+            }
+
+            // Boundary edges of the previous pass get another try with the new radius.
+            let front = (0..self.grid.edges.len() as u32)
+                .filter(|&e| self.grid.edges[e as usize].state != EdgeState::Inner)
+                .collect_vec();
+
+            for &edge in front.iter() {
+                let edge = &mut self.grid.edges[edge as usize];
+                edge.reopen();
+                edge.ball_center = None;
+            }
+
+            self.expand_front(front);
+
+            let mut first_point_index = first_point_index;
+
+            loop {
+                if limit_iterations.is_some_and(|limit| tried_to_expand_counter >= limit) {
+                    return Ok(());
+                }
+
+                let ((e1, e2, e3), seed_point_index) = match self.find_seed_triangle(first_point_index) {
+                    Ok(seed) => seed,
+                    Err(BpaError::NoSeedFound) => break,
+                    Err(e) => return Err(e),
+                };
+                first_point_index = seed_point_index + 1;
+                tried_to_expand_counter += 1;
+
+                self.expand_front(vec![e1, e2, e3]);
+                report(self, first_point_index);
+            }
+
+            report(self, self.points.len());
         }
 
-        let set = triangle_points
-            .iter()
-            .unique_by(|p| p.borrow().id)
-            .cloned()
-            .collect_vec();
+        if self.grid.mesh.num_faces() == 0 {
+            return Err(BpaError::NoSeedFound);
+        }
+
+        Ok(())
+    }
+
+    /// Splits the cloud in `num_workers` slabs of equal point count along its longest axis and
+    /// grows each slab on its own thread with the current radius. A worker also sees the points
+    /// and triangles within one ball diameter of its slab, so its balls are tested against the
+    /// whole neighborhood, but it only builds triangles whose vertices all lie in its slab. The
+    /// new triangles are then merged into `self.grid`, leaving the seams between slabs as
+    /// front edges.
+    fn grow_blocks_in_parallel(&mut self) -> Result<(), BpaError> {
+        let blocks = self.grow_blocks()?;
+        self.add_triangles(blocks.into_iter().flatten());
+
+        Ok(())
+    }
+
+    /// Runs one worker per slab and returns the new triangles of every slab.
+    fn grow_blocks(&self) -> Result<Vec<BlockTriangles>, BpaError> {
+        let num_workers = self.options.num_workers;
+        let options = ReconstructionOptions { radii: vec![self.radius], num_workers: 1, ..self.options.clone() };
+        let halo = 2. * self.radius;
+
+        let extent = |axis: usize| {
+            let (min, max) = self.points.ids()
+                .map(|p| self.points.coords(p)[axis])
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), c| (min.min(c), max.max(c)));
+            max - min
+        };
+        let axis = (0..3).max_by(|&a, &b| extent(a).total_cmp(&extent(b))).unwrap_or(0);
+        let coord = |p: u32| self.points.coords(p)[axis];
+
+        let order = self.points.ids().sorted_by(|&a, &b| coord(a).total_cmp(&coord(b))).collect_vec();
+        let mut rank = vec![0; order.len()];
+        for (i, &p) in order.iter().enumerate() {
+            rank[p as usize] = i;
+        }
+        let block_size = order.len().div_ceil(num_workers);
+
+        std::thread::scope(|scope| {
+            let handles = order.chunks(block_size).enumerate().map(|(i, owned)| {
+                let own_start = i * block_size;
+                let (lo, hi) = (coord(owned[0]) - halo, coord(owned[owned.len() - 1]) + halo);
+                let start = order.partition_point(|&p| coord(p) < lo);
+                let end = order.partition_point(|&p| coord(p) <= hi);
+                let owned = own_start..own_start + owned.len();
+
+                // Seeds are looked for in the same order as without workers.
+                let ids = order[start..end].iter().copied().sorted().collect_vec();
+                let is_owned = ids.iter().map(|&p| owned.contains(&rank[p as usize])).collect_vec();
+                let options = options.clone();
+
+                scope.spawn(move || self.grow_block(&ids, is_owned, options))
+            }).collect_vec();
+
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        })
+    }
+
+    /// Meshes the points `ids`, of which only those flagged in `is_owned` may become vertices,
+    /// starting from the triangles already built on them. Returns the new triangles, with the
+    /// ids of the whole cloud, and their ball radius.
+    fn grow_block(&self, ids: &[u32], is_owned: Vec<bool>, options: ReconstructionOptions) -> Result<BlockTriangles, BpaError> {
+        let mut bpa = match BPA::new(self.points.select(ids), options) {
+            Ok(bpa) => bpa,
+            Err(BpaError::TooFewPoints { .. } | BpaError::DegenerateInput) => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        bpa.is_owned = is_owned;
+
+        let local_ids = ids.iter().enumerate().map(|(i, &p)| (p, i as u32)).collect::<HashMap<_, _>>();
+        bpa.add_triangles(self.grid.mesh.faces().iter().zip(self.grid.triangle_radii.iter()).filter_map(|(triangle, &radius)| {
+            let [a, b, c] = triangle.map(|p| local_ids.get(&p).copied());
+            Some(([a?, b?, c?], radius))
+        }));
+        let num_existing = bpa.grid.mesh.num_faces();
+
+        match bpa.create_mesh(None, 0, &mut |_| {}) {
+            Ok(()) | Err(BpaError::NoSeedFound) => {}
+            Err(e) => return Err(e),
+        }
 
-        let mut rp = None;
+        Ok(bpa.grid.mesh.faces()[num_existing..].iter()
+            .zip(bpa.grid.triangle_radii[num_existing..].iter())
+            .map(|(triangle, &radius)| (triangle.map(|p| ids[p as usize]), radius))
+            .collect())
+    }
+
+    /// Adds `triangles` with their ball radius and their edges, then links the front loops and
+    /// updates the point states, for triangles built without pivoting.
+    fn add_triangles(&mut self, triangles: impl IntoIterator<Item = ([u32; 3], f32)>) {
+        for (triangle, radius) in triangles {
+            let [a, b, c] = triangle;
 
-        for point in set.iter() {
-            if point.borrow().id != p1.borrow().id && point.borrow().id != p2.borrow().id {
-                rp = Some(point.clone());
+            for (p, q) in [(a, b), (b, c), (c, a)] {
+                let edge = self.grid.find_edge(p, q).unwrap_or_else(|| self.grid.add_edge(Edge::new(p, q)));
+                self.grid.edges[edge as usize].add_triangle();
             }
+
+            self.grid.add_triangle(triangle, radius);
         }
 
-        rp
+        self.link_front_loops();
+        for p in self.points.ids() {
+            self.update_point_state(p);
+        }
     }
 
-    pub fn will_triangles_overlap(
-        edge: Rc<RefCell<Edge>>,
-        p3: Rc<RefCell<Point>>,
-        p4: Rc<RefCell<Point>>,
-    ) -> bool {
-        let (p1, p2) = (edge.borrow().p1.clone(), edge.borrow().p2.clone());
-
-        let v1 = [
-            p3.borrow().x - p1.borrow().x,
-            p3.borrow().y - p1.borrow().y,
-            p3.borrow().z - p1.borrow().z,
-        ];
-        let v2 = [
-            p2.borrow().x - p1.borrow().x,
-            p2.borrow().y - p1.borrow().y,
-            p2.borrow().z - p1.borrow().z,
-        ];
-
-        let triangle_normal = vec3_cross(v1, v2);
-
-        let plane_normal = vec3_cross(v2, triangle_normal);
-
-        let v3 = [
-            p4.borrow().x - p1.borrow().x,
-            p4.borrow().y - p1.borrow().y,
-            p4.borrow().z - p1.borrow().z,
-        ];
-
-        vec3_dot(plane_normal, v1).signum() == vec3_dot(plane_normal, v3).signum()
+    /// Pivots the ball around the edges of `front` until no edge can be expanded anymore.
+    /// Front edges around which no triangle could be built become boundary edges.
+    fn expand_front(&mut self, mut front: Vec<u32>) {
+        while let Some(edge) = front.pop() {
+            let Some(new_edges) = self.expand_triangle(edge) else {
+                self.grid.edges[edge as usize].mark_boundary();
+                continue;
+            };
+            front.extend(new_edges.into_iter().filter(|&e| self.grid.edges[e as usize].is_front()));
+        }
     }
 
-    pub fn create_mesh(&mut self, limit_iterations: Option<usize>, first_point_index: usize) {
-        let mut tried_to_expand_counter = 0;
+    /// Pivots the ball around the edge at index `edge` and adds the triangle formed with the
+    /// first point it hits. Returns the indices of the other edges of the new triangles, or
+    /// `None` if the edge is not on the front, has a point the BPA does not own, or the ball hits
+    /// no suitable point.
+    ///
+    /// A point already on the front is only hit next to the edge, where the new triangle glues
+    /// to the front, or together with a second triangle that bridges the gap between the new
+    /// triangle and the others around the point (see [`BPA::find_bridge`]).
+    pub fn expand_triangle(&mut self, edge: u32) -> Option<Vec<u32>> {
+        let Edge { p1, p2, state, ball_center, .. } = self.grid.edges[edge as usize];
+
+        if state != EdgeState::Front || !self.is_owned[p1 as usize] || !self.is_owned[p2 as usize] {
+            return None;
+        }
+
+        let third_point = self.get_third_point_of_edge(p1, p2)?;
+
+        // A loop of three edges around a hole is closed even when it is too thin for any ball
+        // to rest on it.
+        if let Some(p3) = self.find_three_edge_hole(edge).filter(|&p3| p3 != third_point && self.is_owned[p3 as usize] && self.is_wound_along_normals([p1, p3, p2])) {
+            let (e1, e2) = self.add_triangle(edge, p3, None);
+            return Some(vec![e1, e2]);
+        }
+
+        let (a, b, o) = (self.points.coords(p1), self.points.coords(p2), self.points.coords(third_point));
+        let ball_center = ball_center.or_else(|| {
+            let normal = self.calc_normal_hint(&[p1, p2, third_point]);
+            calc_ball_center(a, b, o, self.radius, normal)
+        })?;
+        let circle = PivotCircle::new(a, b, o, ball_center);
+
+        // The ball touches the edge on both ends, so any point it can hit is within
+        // two radii of the edge middle.
+        let possible_points = self.grid.points_within(&self.points, circle.center, 2. * self.radius)
+            .into_iter()
+            .filter(|&p| p != p1 && p != p2 && p != third_point)
+            .collect_vec();
+        let possible_coords = possible_points.iter().map(|&p| self.points.coords(p)).collect_vec();
+
+        let (hit_index, new_ball_center, _) = circle.find_first_hit(a, b, self.radius, &possible_coords)?;
+        let p3 = possible_points[hit_index];
+
+        // The ball only tells which side the triangle faces, a hit folded over the edge still
+        // passes that test but is wound against the point normals.
+        if !self.is_owned[p3 as usize]
+            || self.points.state(p3) == PointState::Inner
+            || !self.has_consistent_normals([p1, p2, p3], new_ball_center)
+            || !self.is_wound_along_normals([p1, p3, p2])
+        {
+            return None;
+        }
+
+        // The new triangle is `p1, p3, p2`, its edges `p1 -> p3` and `p3 -> p2` either are new
+        // or glue to the front edge running the other way.
+        let existing_e1 = self.find_edge(p1, p3);
+        let existing_e2 = self.find_edge(p3, p2);
+
+        if existing_e1.is_some_and(|e| !self.is_front_edge(e, p3, p1)) || existing_e2.is_some_and(|e| !self.is_front_edge(e, p2, p3)) {
+            return None;
+        }
 
-        todo!()
+        let (min_angle, max_angle) = calc_min_max_angle_of_triangle(&self.points, &Edge::new(p1, p2), &Edge::new(p1, p3), &Edge::new(p2, p3));
+
+        if max_angle > self.options.max_angle || min_angle < self.options.min_angle {
+            return None;
+        }
+
+        // Joined to a front point away from the edge, the triangle would only touch the other
+        // triangles of that point at the point itself.
+        let bridge = if self.points.state(p3) == PointState::Front && existing_e1.is_none() && existing_e2.is_none() {
+            Some(self.find_bridge(p1, p2, p3)?)
+        } else {
+            None
+        };
+
+        let (e1, e2) = self.add_triangle(edge, p3, Some(new_ball_center));
+        let mut new_edges = vec![e1, e2];
+
+        if let Some((bridge_edge, bridge_point, bridge_ball_center)) = bridge {
+            let (e1, e2) = self.add_triangle(bridge_edge, bridge_point, Some(bridge_ball_center));
+            new_edges.extend([e1, e2]);
+        }
+
+        Some(new_edges)
     }
 
-    pub fn find_seed_triangle(&mut self, mut first_point_index: usize, num_recursion_calls: usize) -> (isize, (Rc<RefCell<Edge>>, Rc<RefCell<Edge>>, Rc<RefCell<Edge>>), usize) {
-        if num_recursion_calls > self.points.borrow().len() {
-            println!("i was here");
-            panic!("strange shit in \"find_seed_triangle\"")
-            // return (-1, vec![-1, -1]);
+    /// Adds the triangle built on the front edge at index `edge` with the point `p3`, creating
+    /// its two other edges or gluing them to the front edges running the other way. The new
+    /// edges start pivoting from `ball_center`. Returns the indices of these two edges.
+    fn add_triangle(&mut self, edge: u32, p3: u32, ball_center: Option<Vector3<f32>>) -> (u32, u32) {
+        let Edge { p1, p2, .. } = self.grid.edges[edge as usize];
+        let existing_e1 = self.find_edge(p1, p3);
+        let existing_e2 = self.find_edge(p3, p2);
+
+        let e1 = existing_e1.unwrap_or_else(|| self.grid.add_edge(Edge::new(p1, p3)));
+        let e2 = existing_e2.unwrap_or_else(|| self.grid.add_edge(Edge::new(p3, p2)));
+
+        self.grid.edges[edge as usize].add_triangle();
+        for e in [e1, e2] {
+            let e = &mut self.grid.edges[e as usize];
+            e.add_triangle();
+            e.ball_center = ball_center;
         }
 
-        if first_point_index >= (self.points.borrow().len() - 1) {
-            first_point_index = 0;
+        self.grid.join(edge, (e1, existing_e1.is_some()), (e2, existing_e2.is_some()));
+
+        self.grid.add_triangle([p1, p3, p2], self.radius);
+        for p in [p1, p2, p3] {
+            self.update_point_state(p);
         }
 
-        let p1 = self.points.borrow()[first_point_index].clone();
-        let mut p1_neighbor_points = vec![];
+        (e1, e2)
+    }
 
-        for cell in p1.borrow().neighbor_nodes() {
-            p1_neighbor_points.extend(self.grid.get_cell_points(cell));
+    /// Looks for a second triangle to add with the triangle `p1, p3, p2` when `p3` is a front
+    /// point away from the edge `p1 -> p2`: built on the front edge `q -> p3` with `p2`, or on
+    /// the front edge `p3 -> q` with `p1`, it shares an edge with both the new triangle and the
+    /// triangles around `p3`. Returns the front edge, the third point and the ball center of
+    /// the second triangle, `None` if no empty ball rests on either of them.
+    fn find_bridge(&self, p1: u32, p2: u32, p3: u32) -> Option<(u32, u32, Vector3<f32>)> {
+        self.grid.mesh.vertex_neighbors(p3).into_iter().filter(|&q| self.is_owned[q as usize]).find_map(|q| {
+            let edge = self.find_edge(p3, q)?;
+            let (edge, third_point, new_edge) = if self.is_front_edge(edge, q, p3) {
+                (edge, p2, (q, p2))
+            } else if self.is_front_edge(edge, p3, q) {
+                (edge, p1, (p1, q))
+            } else {
+                return None;
+            };
+
+            // The edge between `q` and the new triangle may already run along the front.
+            if self.find_edge(new_edge.0, new_edge.1).is_some_and(|e| !self.is_front_edge(e, new_edge.1, new_edge.0)) {
+                return None;
+            }
+
+            let Edge { p1: a, p2: b, .. } = self.grid.edges[edge as usize];
+            let ball_center = self.find_empty_ball([a, third_point, b])?;
+
+            Some((edge, third_point, ball_center))
+        })
+    }
+
+    /// Returns the center of an empty ball resting on `triangle`, on the side of its point
+    /// normals, if the triangle is wound along them and its angles are within the limits.
+    fn find_empty_ball(&self, triangle: [u32; 3]) -> Option<Vector3<f32>> {
+        if !self.is_wound_along_normals(triangle) {
+            return None;
         }
 
-        let p1_neighbor_points = p1_neighbor_points.iter().unique_by(|&p| p.borrow().id)
-            .map(|p| p.clone()).collect_vec();
+        let [p1, p2, p3] = triangle;
+        let (min_angle, max_angle) = calc_min_max_angle_of_triangle(&self.points, &Edge::new(p1, p2), &Edge::new(p1, p3), &Edge::new(p2, p3));
 
-        let dists = p1_neighbor_points.iter().map(|p2| calc_distance_points(p1.clone(), p2.clone())).collect_vec();
-        let p1_neighbor_points = dists.iter().zip(p1_neighbor_points).
-            sorted_by(|(d1, p1), (d2,p2)| d1.partial_cmp(d2).unwrap_or(Equal))
-            .map(|(d, p)| p.clone()).collect_vec();
+        if max_angle > self.options.max_angle || min_angle < self.options.min_angle {
+            return None;
+        }
 
-        let limit_points = 6;
-        let p1_neighbor_points = p1_neighbor_points[..=limit_points].iter().collect_vec();
+        let [a, b, c] = triangle.map(|p| self.points.coords(p));
+        let ball_center = calc_ball_center(a, b, c, self.radius, self.calc_normal_hint(&triangle))?;
+        let neighbors = self.grid.points_within(&self.points, ball_center, self.radius)
+            .into_iter()
+            .filter(|p| !triangle.contains(p))
+            .map(|p| self.points.coords(p))
+            .collect_vec();
 
-        for p2 in p1_neighbor_points {
-            // if p2.borrow().is_used {
-            //
-            // }
+        (is_ball_empty(ball_center, self.radius, &neighbors) && self.has_consistent_normals(triangle, ball_center))
+            .then_some(ball_center)
+    }
 
-            if p2.borrow().x == p1.borrow().x && p2.borrow().y == p1.borrow().y && p2.borrow().z == p1.borrow().z {
-                continue
+    /// Returns the third point of the front loop of `edge` if the loop has three edges.
+    fn find_three_edge_hole(&self, edge: u32) -> Option<u32> {
+        let next = self.grid.edges[edge as usize].next?;
+        let last = self.grid.edges[next as usize].next?;
+
+        (self.grid.edges[last as usize].next == Some(edge)).then_some(self.grid.edges[next as usize].p2)
+    }
+
+    /// Checks that `edge` is on the front and runs from `from` to `to`.
+    fn is_front_edge(&self, edge: u32, from: u32, to: u32) -> bool {
+        let edge = &self.grid.edges[edge as usize];
+        edge.is_on_front() && edge.p1 == from && edge.p2 == to
+    }
+
+    /// Sets the state of `p` from its edges: unused without edges, inner if all its edges
+    /// are shared by two triangles, on the front otherwise.
+    fn update_point_state(&mut self, p: u32) {
+        let neighbors = self.grid.mesh.vertex_neighbors(p);
+
+        self.points.states[p as usize] = if neighbors.is_empty() {
+            PointState::Unused
+        } else if neighbors.iter().any(|&q| self.find_edge(p, q).is_some_and(|e| self.grid.edges[e as usize].is_on_front())) {
+            PointState::Front
+        } else {
+            PointState::Inner
+        };
+    }
+
+    /// Orients the front and boundary edges along the boundary of the mesh and links them in
+    /// loops, for edges built without pivoting.
+    fn link_front_loops(&mut self) {
+        let boundary = self.grid.mesh.boundary_half_edges().collect_vec();
+
+        for &h in boundary.iter() {
+            let (from, to) = (self.grid.mesh.origin(h), self.grid.mesh.dest(h));
+            if let Some(e) = self.find_edge(from, to) {
+                let e = &mut self.grid.edges[e as usize];
+                (e.p1, e.p2, e.prev, e.next) = (from, to, None, None);
             }
+        }
 
-            let mut intersect_cells = p1.borrow().neighbor_nodes();
-            intersect_cells.extend(p2.borrow().neighbor_nodes());
-            let intersect_cells = intersect_cells.iter().unique().collect_vec();
+        for &h in boundary.iter() {
+            let Some(e) = self.find_edge(self.grid.mesh.origin(h), self.grid.mesh.dest(h)) else { continue };
+            let to = self.grid.edges[e as usize].p2;
 
-            let mut possible_points = vec![];
+            let next = self.grid.mesh.outgoing(to).iter()
+                .filter(|&&g| self.grid.mesh.is_boundary_half_edge(g))
+                .filter_map(|&g| self.find_edge(to, self.grid.mesh.dest(g)))
+                .find(|&g| self.grid.edges[g as usize].prev.is_none());
 
-            for cell in intersect_cells{
-                possible_points.extend(self.grid.get_cell_points(*cell));
+            if let Some(next) = next {
+                self.grid.link_edges(Some(e), Some(next));
             }
+        }
+    }
 
-            let dists_p2 = possible_points.iter().map(|p3| calc_distance_points(p2.clone(), p3.clone())).collect_vec();
-            let dists_p1 = possible_points.iter().map(|p3| calc_distance_points(p1.clone(), p3.clone())).collect_vec();
+    /// Looks for a seed triangle around the unused points, starting at `first_point_index` and
+    /// wrapping around. Returns the seed edges and the index of the point it was found from.
+    pub fn find_seed_triangle(&mut self, mut first_point_index: usize) -> Result<(SeedTriangle, usize), BpaError> {
+        let num_points = self.points.len();
 
-            let dists = (0..dists_p1.len()).into_iter().map(|i| dists_p1[i] + dists_p2[i]).collect_vec();
-            let possible_points = dists.iter().zip(possible_points).
-                sorted_by(|(d1, p1), (d2,p2)| d1.partial_cmp(d2).unwrap_or(Equal))
-                .map(|(d, p)| p.clone()).collect_vec();
+        for _ in 0..num_points {
+            if first_point_index >= num_points {
+                first_point_index = 0;
+            }
 
-            let limit_points = 5;
-            let possible_points = possible_points[..limit_points].iter().collect_vec();
+            if let Some(seed) = self.find_seed_triangle_from(first_point_index as u32) {
+                return Ok((seed, first_point_index));
+            }
 
-            for p3 in possible_points.iter().cloned() {
-                if (p3.borrow().x == p1.borrow().x && p3.borrow().y == p1.borrow().y && p3.borrow().z == p1.borrow().z)
-                    || (p2.borrow().x == p3.borrow().x && p2.borrow().y == p3.borrow().y && p2.borrow().z == p3.borrow().z) {
-                    continue;
-                }
+            first_point_index += 1;
+        }
+
+        Err(BpaError::NoSeedFound)
+    }
 
-                if self.radius <= calc_incircle_radius(p1.clone(), p2.clone(), p3.clone()) {
-                    let v1 = [p2.borrow().x - p1.borrow().x, p2.borrow().y - p1.borrow().y, p2.borrow().z- p1.borrow().z];
-                    let v2 = [p3.borrow().x - p1.borrow().x, p3.borrow().y - p1.borrow().y, p3.borrow().z- p1.borrow().z];
+    fn find_seed_triangle_from(&mut self, p1: u32) -> Option<SeedTriangle> {
+        if self.points.state(p1) != PointState::Unused || !self.is_owned[p1 as usize] {
+            return None;
+        }
 
-                    let triangle_normal = vec3_cross(v1, v2);
+        // Every point a ball touching `p1` can touch or contain is within two radii of `p1`.
+        let p1_neighbor_points = self.grid.k_nearest(&self.points, p1, self.options.seed_neighbors, 2. * self.radius);
+        let possible_points = self.grid.points_within(&self.points, self.points.coords(p1), 2. * self.radius);
 
-                    //TODO: Check if the normal of the triangle is on the same direction with points normals.
-                    //https://github.com/Lotemn102/Ball-Pivoting-Algorithm/blob/34edabac94a4ecbc01741ecc76df0d24cd6f1e2c/bpa.py#L262
+        for p2 in p1_neighbor_points {
+            if !self.is_owned[p2 as usize] || self.points.state(p2) != PointState::Unused || self.points.coords(p2) == self.points.coords(p1) {
+                continue
+            }
 
+            let possible_points = possible_points.clone();
 
-                    // p1_and_p3_already_connected = [e for e in self.grid.edges if ((e.p1.id == p1.id)
-                    // and (e.p2.id == p3.id)) or ((e.p1.id == p3.id) and (e.p2.id == p1.id))]
-                    let p1_and_p3_already_connected = self.grid.edges.iter().filter(|&e| {
-                        let eb = e.borrow();
-                        ((eb.p1.borrow().id == p1.borrow().id) && (eb.p2.borrow().id == p3.borrow().id)) ||
-                            ((eb.p1.borrow().id == p3.borrow().id) && (eb.p2.borrow().id == p1.borrow().id))
-                    }).collect_vec();
+            let dists = Self::get_points_distances_from_edge(&self.points, &possible_points, p1, p2);
+            let possible_points = dists.iter().zip(possible_points).
+                sorted_by(|(d1, _), (d2, _)| d1.partial_cmp(d2).unwrap_or(Equal))
+                .map(|(_, p)| p).collect_vec();
 
-                    let p1_and_p2_already_connected = self.grid.edges.iter().filter(|&e| {
-                        let eb = e.borrow();
-                        ((eb.p1.borrow().id == p1.borrow().id) && (eb.p2.borrow().id == p2.borrow().id)) ||
-                            ((eb.p1.borrow().id == p2.borrow().id) && (eb.p2.borrow().id == p1.borrow().id))
-                    }).collect_vec();
+            let possible_coords = possible_points.iter().map(|&p| self.points.coords(p)).collect_vec();
 
-                    let p2_and_p3_already_connected = self.grid.edges.iter().filter(|&e| {
-                        let eb = e.borrow();
-                        ((eb.p1.borrow().id == p2.borrow().id) && (eb.p2.borrow().id == p3.borrow().id)) ||
-                            ((eb.p1.borrow().id == p3.borrow().id) && (eb.p2.borrow().id == p2.borrow().id))
-                    }).collect_vec();
+            for &p3 in possible_points.iter().take(self.options.seed_candidates) {
+                if !self.is_owned[p3 as usize]
+                    || self.points.state(p3) != PointState::Unused
+                    || self.points.coords(p3) == self.points.coords(p1)
+                    || self.points.coords(p3) == self.points.coords(p2)
+                {
+                    continue;
+                }
 
-                    if p1_and_p3_already_connected.len() > 0 || p1_and_p2_already_connected.len() > 0 || p2_and_p3_already_connected.len() >0 {
-                        continue;
-                    }
+                let (a, b, c) = (self.points.coords(p1), self.points.coords(p2), self.points.coords(p3));
+                let normal = self.calc_normal_hint(&[p1, p2, p3]);
+                let ball_center = calc_ball_center(a, b, c, self.radius, normal)
+                    .filter(|&center| is_ball_empty(center, self.radius, &possible_coords));
 
-                    let are_p1_p3_closing_another_triangle_in_the_mesh =
-                        self.is_there_a_path_between_two_points(p1.clone(), p3.clone(), p2.clone());
-                    let are_p2_p3_closing_another_triangle_in_the_mesh =
-                        self.is_there_a_path_between_two_points(p2.clone(), p3.clone(), p1.clone());
-                    let are_p1_p2_closing_another_triangle_in_the_mesh =
-                        self.is_there_a_path_between_two_points(p1.clone(), p2.clone(), p3.clone());
-
-                    let mut e1 = Edge::new(p1.clone(), p3.clone());
-                    e1.borrow_mut().num_triangles_this_edge_in += 1;
-                    if are_p1_p3_closing_another_triangle_in_the_mesh {
-                        e1.borrow_mut().num_triangles_this_edge_in += 1;
-                    }
-                    let mut e2 = Edge::new(p1.clone(), p2.clone());
-                    e2.borrow_mut().num_triangles_this_edge_in += 1;
-                    if are_p1_p2_closing_another_triangle_in_the_mesh {
-                        e2.borrow_mut().num_triangles_this_edge_in += 1;
-                    }
-                    let mut e3 = Edge::new(p2.clone(), p3.clone());
-                    e3.borrow_mut().num_triangles_this_edge_in += 1;
-                    if are_p2_p3_closing_another_triangle_in_the_mesh {
-                        e3.borrow_mut().num_triangles_this_edge_in += 1;
+                if let Some(ball_center) = ball_center {
+                    if !self.has_consistent_normals([p1, p2, p3], ball_center) {
+                        continue;
                     }
 
-                    let (min_angle, max_angle) = calc_min_max_angle_of_triangle(e1.clone(), e2.clone(), e3.clone());
+                    let (min_angle, max_angle) = calc_min_max_angle_of_triangle(&self.points, &Edge::new(p1, p3), &Edge::new(p1, p2), &Edge::new(p2, p3));
 
-                    if max_angle > 170. || min_angle < 20. {
+                    if max_angle > self.options.max_angle || min_angle < self.options.min_angle {
                         continue
                     }
 
-                    self.grid.edges.push(e1.clone());
-                    self.grid.edges.push(e2.clone());
-                    self.grid.edges.push(e3.clone());
-
-                    let mut triangle =
-                        [e1.borrow().p1.clone(), e1.borrow().p2.clone(), e2.borrow().p1.clone(), e2.borrow().p2.clone(),e3.borrow().p1.clone(), e3.borrow().p2.clone()];
-                    triangle.sort_by(|p1, p2| {p1.borrow().z.total_cmp(&p2.borrow().z)});
+                    // The three points are unused, so the seed starts a new front loop.
+                    let triangle = self.orient_triangle([p1, p2, p3]);
+                    let [e1, e2, e3] = [0, 1, 2].map(|i| {
+                        let mut e = Edge::new(triangle[i], triangle[(i + 1) % 3]);
+                        e.add_triangle();
+                        e.ball_center = Some(ball_center);
+                        self.grid.add_edge(e)
+                    });
+                    for (from, to) in [(e1, e2), (e2, e3), (e3, e1)] {
+                        self.grid.link_edges(Some(from), Some(to));
+                    }
 
-                    self.grid.triangles.push(triangle);
-                    self.first_free_point_index += 1;
+                    self.grid.add_triangle(triangle, self.radius);
 
-                    p1.borrow_mut().is_used = true;
-                    p2.borrow_mut().is_used = true;
-                    p2.borrow_mut().is_used = true;
+                    for p in triangle {
+                        self.points.states[p as usize] = PointState::Front;
+                    }
 
-                    return (1, (e1, e2, e3), first_point_index);
+                    return Some((e1, e2, e3));
                 }
             }
         }
 
-        self.find_seed_triangle(first_point_index + 1, num_recursion_calls +1)
+        None
     }
 
-    fn is_there_a_path_between_two_points(&self, p1: Rc<RefCell<Point>>, p2: Rc<RefCell<Point>>, point_of_triangle_we_creating: Rc<RefCell<Point>>) -> bool {
-        let mut edges_first_point_int = vec![];
-        let mut edges_second_point_int = vec![];
-        let mut points_first_edges = vec![];
-        let mut points_second_edges = vec![];
+    /// Checks that the normal of the triangle, oriented towards the ball resting on it,
+    /// points to the same side as the normals of its three points.
+    fn has_consistent_normals(&self, triangle: [u32; 3], ball_center: Vector3<f32>) -> bool {
+        let [a, b, c] = triangle.map(|p| self.points.coords(p));
+        let triangle_normal = calc_oriented_normal(a, b, c, ball_center);
 
-        for e in self.grid.edges.iter() {
-            if p1.borrow().id == e.borrow().p1.borrow().id || p1.borrow().id == e.borrow().p2.borrow().id {
-                edges_first_point_int.push(e.clone());
-            }
+        triangle.iter().all(|&p| self.points.normal(p).is_some_and(|n| vec3_dot(n, triangle_normal) > 0.))
+    }
 
-            if p2.borrow().id == e.borrow().p1.borrow().id || p2.borrow().id == e.borrow().p2.borrow().id {
-                edges_second_point_int.push(e.clone());
-            }
-        }
+    /// Sums the normals of `points`, `None` if any of them has no normal.
+    fn calc_normal_hint(&self, points: &[u32]) -> Option<Vector3<f32>> {
+        points.iter().try_fold([0., 0., 0.], |sum, &p| self.points.normal(p).map(|n| vec3_add(sum, n)))
+    }
 
-        for e in edges_first_point_int.iter() {
-            points_first_edges.push(e.borrow().p1.borrow().id);
-            points_first_edges.push(e.borrow().p2.borrow().id);
-        }
-        for e in edges_second_point_int.iter() {
-            points_second_edges.push(e.borrow().p1.borrow().id);
-            points_second_edges.push(e.borrow().p2.borrow().id);
+    /// Returns `triangle` wound counter-clockwise around the normals of its points.
+    fn orient_triangle(&self, triangle: [u32; 3]) -> [u32; 3] {
+        if self.is_wound_along_normals(triangle) {
+            triangle
+        } else {
+            [triangle[0], triangle[2], triangle[1]]
         }
+    }
 
-        let mut points_first_edges = points_first_edges.iter().unique_by(|&p| p).map(|p| p.clone()).collect_vec();
-        if points_first_edges.contains(&(p1.borrow().id)) {
-            points_first_edges.remove(points_first_edges.iter().position(|x| *x == p1.borrow().id).unwrap());
-        }
+    /// Checks that `triangle` is wound counter-clockwise around the sum of the normals of its
+    /// points.
+    fn is_wound_along_normals(&self, triangle: [u32; 3]) -> bool {
+        let [a, b, c] = triangle.map(|p| self.points.coords(p));
+        let normal = calc_triangle_normal(a, b, c);
 
-        let mut points_second_edges = points_second_edges.iter().unique_by(|&p| p).map(|p| p.clone()).collect_vec();
-        if points_second_edges.contains(&(p1.borrow().id)) {
-            points_second_edges.remove(points_second_edges.iter().position(|x| *x == p1.borrow().id).unwrap());
-        }
+        self.calc_normal_hint(&triangle).is_none_or(|hint| vec3_dot(normal, hint) >= 0.)
+    }
 
-        points_first_edges.extend(points_second_edges);
-        let mut intersection = points_first_edges.iter().unique().map(|id| *id).collect_vec();
+    /// Returns the index of the edge connecting `p1` and `p2`, if it is already part of the mesh.
+    fn find_edge(&self, p1: u32, p2: u32) -> Option<u32> {
+        self.grid.find_edge(p1, p2)
+    }
 
-        if intersection.contains(&point_of_triangle_we_creating.borrow().id) {
-            intersection.remove(intersection.iter().position(|x| *x == point_of_triangle_we_creating.borrow().id).unwrap());
-        }
+    /// Returns the third point of a triangle already built on the edge `p1`-`p2`.
+    fn get_third_point_of_edge(&self, p1: u32, p2: u32) -> Option<u32> {
+        let face = self.grid.mesh.edge_faces(p1, p2).next()?;
 
-        intersection.len() > 0
+        self.grid.mesh.face(face).into_iter().find(|&p| p != p1 && p != p2)
     }
 }
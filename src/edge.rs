@@ -1,20 +1,82 @@
-use std::{cell::RefCell, rc::Rc};
+use vecmath::Vector3;
 
-use crate::point::Point;
+/// Stage of an edge in the ball pivoting front.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgeState {
+    /// Edge of a single triangle that the ball can still pivot around.
+    #[default]
+    Front,
+    /// Edge of a single triangle around which pivoting found no point, the surface stays
+    /// open there unless a pass with a larger ball closes it.
+    Boundary,
+    /// Edge shared by two triangles.
+    Inner,
+}
 
-#[derive(Clone, PartialEq)]
+/// Edge between two points of a [`Points`](crate::point::Points) store, given by index.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Edge {
-    pub p1: Rc<RefCell<Point>>,
-    pub p2: Rc<RefCell<Point>>,
+    pub p1: u32,
+    pub p2: u32,
     pub num_triangles_this_edge_in: usize,
+    pub state: EdgeState,
+    /// Previous and next edges of the front loop, for front and boundary edges. The loop
+    /// runs from `p1` to `p2`, counter-clockwise around the surface.
+    pub prev: Option<u32>,
+    pub next: Option<u32>,
+    /// Center of the ball resting on the last triangle added on this edge.
+    pub ball_center: Option<Vector3<f32>>,
 }
 
 impl Edge {
-    pub fn new(p1: Rc<RefCell<Point>>, p2: Rc<RefCell<Point>>) -> Rc<RefCell<Edge>> {
-        Rc::new(RefCell::new(Edge {
+    pub fn new(p1: u32, p2: u32) -> Edge {
+        Edge {
             p1,
             p2,
             num_triangles_this_edge_in: 0,
-        }))
+            state: EdgeState::Front,
+            prev: None,
+            next: None,
+            ball_center: None,
+        }
+    }
+
+    /// Returns true if the edge connects `p1` and `p2`, in either direction.
+    pub fn connects(&self, p1: u32, p2: u32) -> bool {
+        (self.p1 == p1 && self.p2 == p2) || (self.p1 == p2 && self.p2 == p1)
+    }
+
+    pub fn contains(&self, p: u32) -> bool {
+        self.p1 == p || self.p2 == p
+    }
+
+    /// Records one more triangle on the edge: its first triangle puts it on the front, its
+    /// second one makes it inner.
+    pub fn add_triangle(&mut self) {
+        self.num_triangles_this_edge_in += 1;
+        self.state = if self.num_triangles_this_edge_in >= 2 { EdgeState::Inner } else { EdgeState::Front };
+    }
+
+    /// Marks a front edge around which the ball could not pivot as boundary.
+    pub fn mark_boundary(&mut self) {
+        if self.state == EdgeState::Front {
+            self.state = EdgeState::Boundary;
+        }
+    }
+
+    /// Puts a boundary edge back on the front, for a pass with another ball radius.
+    pub fn reopen(&mut self) {
+        if self.state == EdgeState::Boundary {
+            self.state = EdgeState::Front;
+        }
+    }
+
+    pub fn is_front(&self) -> bool {
+        self.state == EdgeState::Front
+    }
+
+    /// Returns true for front and boundary edges, which are linked in the front loops.
+    pub fn is_on_front(&self) -> bool {
+        self.state != EdgeState::Inner
     }
 }
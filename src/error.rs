@@ -0,0 +1,43 @@
+use std::fmt;
+
+/// Errors returned by the reconstruction pipeline instead of panicking on bad input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BpaError {
+    /// No triangle could be seeded, typically because the radius is too small or too large
+    /// for the point spacing.
+    NoSeedFound,
+    /// At least `required` points are needed, only `found` were given.
+    TooFewPoints { found: usize, required: usize },
+    /// The point has no normal, ball pivoting needs them to orient the triangles.
+    MissingNormal { point_id: u32 },
+    /// The point has a NaN or infinite coordinate.
+    NonFiniteCoordinate { point_id: u32 },
+    /// All the points are at the same position, or too close to estimate their spacing.
+    DegenerateInput,
+    /// Ball radii must be finite and positive.
+    InvalidRadius(f32),
+    /// There must be exactly one normal per point.
+    LengthMismatch { points: usize, normals: usize },
+}
+
+impl fmt::Display for BpaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BpaError::NoSeedFound => write!(f, "no seed triangle found"),
+            BpaError::TooFewPoints { found, required } => {
+                write!(f, "too few points: {found} given, at least {required} needed")
+            }
+            BpaError::MissingNormal { point_id } => write!(f, "point {point_id} has no normal"),
+            BpaError::NonFiniteCoordinate { point_id } => {
+                write!(f, "point {point_id} has a non-finite coordinate")
+            }
+            BpaError::DegenerateInput => write!(f, "all points are at the same position"),
+            BpaError::InvalidRadius(radius) => write!(f, "invalid ball radius {radius}"),
+            BpaError::LengthMismatch { points, normals } => {
+                write!(f, "{points} points given with {normals} normals")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BpaError {}
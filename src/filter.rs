@@ -0,0 +1,178 @@
+use vecmath::{vec3_add, vec3_dot, vec3_len, vec3_normalized, vec3_scale, vec3_sub, Vector3};
+
+use crate::grid::Grid;
+use crate::point::Points;
+
+/// Point kept for each voxel by [`voxel_downsample`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VoxelRepresentative {
+    /// The centroid of the points of the voxel.
+    Centroid,
+    /// The point of the voxel closest to their centroid, so that output points are input points.
+    NearestPoint,
+}
+
+/// Replaces the points of each cubic voxel of side `voxel_size` by a single point, in the
+/// order of the first point of each voxel.
+///
+/// Voxels are the cells of a [`Grid`], so they grow if the bounding box would span more cells
+/// than a cell code can hold. The normal of a voxel is the average of the normals of its
+/// points, flipped to agree with the first one, and normalized. Colors and intensities are
+/// averaged. A `voxel_size` that is not positive returns a copy of the cloud.
+pub fn voxel_downsample(points: &Points, voxel_size: f32, representative: VoxelRepresentative) -> Points {
+    if voxel_size.is_nan() || voxel_size <= 0. || points.is_empty() {
+        return points.clone();
+    }
+
+    let grid = Grid::new(0.5 * voxel_size, points);
+
+    let mut voxels = grid.cells.values().collect::<Vec<_>>();
+    voxels.sort_unstable_by_key(|voxel| voxel[0]);
+
+    let mut downsampled = Points::with_capacity(voxels.len());
+
+    for voxel in voxels {
+        let centroid = mean(voxel.iter().map(|&p| points.coords(p)));
+        let coords = match representative {
+            VoxelRepresentative::Centroid => centroid,
+            VoxelRepresentative::NearestPoint => {
+                let distance = |p: u32| vec3_len(vec3_sub(points.coords(p), centroid));
+                let nearest = voxel.iter().copied().min_by(|&a, &b| distance(a).total_cmp(&distance(b))).unwrap();
+                points.coords(nearest)
+            }
+        };
+
+        push_group(&mut downsampled, points, voxel, coords);
+    }
+
+    downsampled
+}
+
+/// Returns, in increasing order, the points whose mean distance to their `k` nearest
+/// neighbors is at most `std_ratio` standard deviations above the mean over the cloud.
+///
+/// Use [`Points::select`] on the result to keep the attributes aligned.
+pub fn remove_statistical_outliers(points: &Points, k: usize, std_ratio: f32) -> Vec<u32> {
+    if points.len() < 2 || k == 0 {
+        return points.ids().collect();
+    }
+
+    let grid = Grid::for_neighbor_queries(points, k);
+    let mean_distances = points
+        .ids()
+        .map(|p| {
+            let neighbors = grid.k_nearest(points, p, k, f32::INFINITY);
+            let sum = neighbors.iter().map(|&q| vec3_len(vec3_sub(points.coords(p), points.coords(q)))).sum::<f32>();
+            sum / neighbors.len().max(1) as f32
+        })
+        .collect::<Vec<_>>();
+
+    let len = mean_distances.len() as f64;
+    let mean = mean_distances.iter().map(|&d| d as f64).sum::<f64>() / len;
+    let variance = mean_distances.iter().map(|&d| (d as f64 - mean).powi(2)).sum::<f64>() / len;
+    let max_distance = mean + std_ratio as f64 * variance.sqrt();
+
+    points.ids().filter(|&p| mean_distances[p as usize] as f64 <= max_distance).collect()
+}
+
+/// Returns, in increasing order, the points that have at least `min_neighbors` other points
+/// within distance `radius`.
+///
+/// Use [`Points::select`] on the result to keep the attributes aligned.
+pub fn remove_radius_outliers(points: &Points, min_neighbors: usize, radius: f32) -> Vec<u32> {
+    let grid = Grid::new(0.5 * radius, points);
+
+    points
+        .ids()
+        .filter(|&p| grid.points_within(points, points.coords(p), radius).len() > min_neighbors)
+        .collect()
+}
+
+/// Result of [`merge_duplicates`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergedPoints {
+    pub points: Points,
+    /// Id in `points` of every input point, indexed by input id.
+    pub remap: Vec<u32>,
+}
+
+/// Merges the points closer than `epsilon` to each other. With `epsilon = 0` only points at
+/// exactly the same position are merged.
+///
+/// Points are visited in id order, each point not merged yet absorbs the points not merged
+/// yet within `epsilon` of it. A merged point is at the centroid of its group, its normal,
+/// color and intensity are averaged as in [`voxel_downsample`]. Merged points are in the
+/// order of the first point of their group.
+pub fn merge_duplicates(points: &Points, epsilon: f32) -> MergedPoints {
+    let epsilon = epsilon.max(0.);
+    let grid = Grid::new(0.5 * epsilon, points);
+
+    let mut merged = Points::new();
+    let mut remap = vec![u32::MAX; points.len()];
+    let mut group = vec![];
+
+    for p in points.ids() {
+        if remap[p as usize] != u32::MAX {
+            continue;
+        }
+
+        group.clear();
+        group.extend(
+            grid.points_within(points, points.coords(p), epsilon)
+                .into_iter()
+                .filter(|&q| remap[q as usize] == u32::MAX),
+        );
+        if !group.contains(&p) {
+            group.push(p);
+        }
+        // Ids are sorted so that the first point of the group gives the first normal.
+        group.sort_unstable();
+
+        let centroid = mean(group.iter().map(|&q| points.coords(q)));
+        let id = push_group(&mut merged, points, &group, centroid);
+
+        for &q in group.iter() {
+            remap[q as usize] = id;
+        }
+    }
+
+    MergedPoints { points: merged, remap }
+}
+
+/// Adds a point at `coords` to `target` with the average normal, color and intensity of the
+/// points `group`, and returns its id.
+fn push_group(target: &mut Points, points: &Points, group: &[u32], coords: Vector3<f32>) -> u32 {
+    let [x, y, z] = coords;
+    let id = target.push(x, y, z, average_normal(group.iter().filter_map(|&p| points.normal(p))));
+
+    if points.colors.is_some() {
+        let color = mean(group.iter().map(|&p| points.color(p).unwrap().map(f32::from)));
+        target.set_color(id, color.map(|c| c.round() as u8));
+    }
+    if points.intensities.is_some() {
+        let sum = group.iter().map(|&p| points.intensity(p).unwrap()).sum::<f32>();
+        target.set_intensity(id, sum / group.len() as f32);
+    }
+
+    id
+}
+
+fn mean(vectors: impl ExactSizeIterator<Item = Vector3<f32>>) -> Vector3<f32> {
+    let len = vectors.len().max(1) as f32;
+    vec3_scale(vectors.fold([0.; 3], vec3_add), 1. / len)
+}
+
+/// Returns the normalized sum of `normals`, each flipped to agree with the first one, `None`
+/// if there are none or they cancel out.
+fn average_normal(mut normals: impl Iterator<Item = Vector3<f32>>) -> Option<Vector3<f32>> {
+    let first = normals.next()?;
+    let sum = normals.fold(first, |sum, n| {
+        if vec3_dot(n, first) < 0. {
+            vec3_sub(sum, n)
+        } else {
+            vec3_add(sum, n)
+        }
+    });
+
+    (vec3_len(sum) > 0.).then(|| vec3_normalized(sum))
+}
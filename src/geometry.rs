@@ -0,0 +1,162 @@
+use std::f32::consts::PI;
+
+use vecmath::{vec3_add, vec3_cross, vec3_dot, vec3_len, vec3_normalized, vec3_scale, vec3_square_len, vec3_sub, Vector3};
+
+/// Angles below this value are treated as "the ball did not move".
+const ANGLE_EPSILON: f32 = 1e-5;
+
+/// Returns the center of the circle through `a`, `b` and `c`, or `None` for collinear points.
+pub fn calc_circumcenter(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Option<Vector3<f32>> {
+    let ab = vec3_sub(b, a);
+    let ac = vec3_sub(c, a);
+    let n = vec3_cross(ab, ac);
+    let n_len_sq = vec3_square_len(n);
+
+    if n_len_sq <= f32::EPSILON * vec3_square_len(ab) * vec3_square_len(ac) {
+        return None;
+    }
+
+    let offset = vec3_add(
+        vec3_scale(vec3_cross(n, ab), vec3_square_len(ac)),
+        vec3_scale(vec3_cross(ac, n), vec3_square_len(ab)),
+    );
+
+    Some(vec3_add(a, vec3_scale(offset, 1. / (2. * n_len_sq))))
+}
+
+/// Returns the unit normal of the triangle `a`, `b`, `c` following its winding.
+pub fn calc_triangle_normal(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Vector3<f32> {
+    vec3_normalized(vec3_cross(vec3_sub(b, a), vec3_sub(c, a)))
+}
+
+/// Returns the unit normal of the triangle `a`, `b`, `c` pointing to the side of `ball_center`.
+pub fn calc_oriented_normal(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>, ball_center: Vector3<f32>) -> Vector3<f32> {
+    let normal = calc_triangle_normal(a, b, c);
+
+    if vec3_dot(normal, vec3_sub(ball_center, a)) < 0. {
+        vec3_scale(normal, -1.)
+    } else {
+        normal
+    }
+}
+
+/// Returns both centers of a ball of `radius` touching `a`, `b` and `c`.
+/// The first one lies on the side of the triangle normal, the second one on the opposite side.
+/// `None` if the points are collinear or too far apart for the ball to touch all three.
+pub fn calc_ball_centers(
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+    radius: f32,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let circumcenter = calc_circumcenter(a, b, c)?;
+    let height_sq = radius.powi(2) - vec3_square_len(vec3_sub(a, circumcenter));
+
+    if height_sq < 0. {
+        return None;
+    }
+
+    let offset = vec3_scale(calc_triangle_normal(a, b, c), height_sq.sqrt());
+    Some((vec3_add(circumcenter, offset), vec3_sub(circumcenter, offset)))
+}
+
+/// Returns the center of a ball of `radius` touching `a`, `b` and `c`.
+/// The ball is put on the side `normal` points to, or on the side of the triangle normal
+/// when no normal is given.
+pub fn calc_ball_center(
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    c: Vector3<f32>,
+    radius: f32,
+    normal: Option<Vector3<f32>>,
+) -> Option<Vector3<f32>> {
+    let (front, back) = calc_ball_centers(a, b, c, radius)?;
+
+    match normal {
+        Some(normal) if vec3_dot(calc_triangle_normal(a, b, c), normal) < 0. => Some(back),
+        _ => Some(front),
+    }
+}
+
+/// Returns true if no point of `points` lies strictly inside the ball.
+pub fn is_ball_empty(center: Vector3<f32>, radius: f32, points: &[Vector3<f32>]) -> bool {
+    let limit = radius * (1. - 1e-3);
+    points.iter().all(|&p| vec3_len(vec3_sub(p, center)) >= limit)
+}
+
+/// The circle swept by the center of the ball while it pivots around the edge `a`-`b`.
+pub struct PivotCircle {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+    /// Rotation axis, oriented so that a positive rotation moves the ball away from the
+    /// triangle it currently rests on.
+    pub axis: Vector3<f32>,
+    /// Unit vector from `center` to the starting ball center.
+    pub start: Vector3<f32>,
+}
+
+impl PivotCircle {
+    /// `opposite` is the third point of the triangle the ball rests on, `ball_center` its center.
+    pub fn new(a: Vector3<f32>, b: Vector3<f32>, opposite: Vector3<f32>, ball_center: Vector3<f32>) -> PivotCircle {
+        let center = vec3_scale(vec3_add(a, b), 0.5);
+        let start = vec3_sub(ball_center, center);
+        let mut axis = vec3_normalized(vec3_sub(b, a));
+
+        if vec3_dot(vec3_cross(axis, start), vec3_sub(opposite, center)) > 0. {
+            axis = vec3_scale(axis, -1.);
+        }
+
+        PivotCircle {
+            center,
+            radius: vec3_len(start),
+            axis,
+            start: vec3_normalized(start),
+        }
+    }
+
+    /// Returns the angle in `[0, 2π)` the ball has to pivot for its center to reach `ball_center`.
+    pub fn calc_angle(&self, ball_center: Vector3<f32>) -> f32 {
+        let v = vec3_sub(ball_center, self.center);
+        let sin = vec3_dot(self.axis, vec3_cross(self.start, v));
+        let cos = vec3_dot(self.start, v);
+        let angle = sin.atan2(cos);
+
+        if angle < 0. {
+            angle + 2. * PI
+        } else {
+            angle
+        }
+    }
+
+    /// Pivots a ball of `radius` around the edge `a`-`b` and returns the first of `candidates`
+    /// it hits, together with the new ball center and the pivot angle.
+    pub fn find_first_hit(
+        &self,
+        a: Vector3<f32>,
+        b: Vector3<f32>,
+        radius: f32,
+        candidates: &[Vector3<f32>],
+    ) -> Option<(usize, Vector3<f32>, f32)> {
+        let mut first_hit: Option<(usize, Vector3<f32>, f32)> = None;
+
+        for (i, &p) in candidates.iter().enumerate() {
+            let Some((front, back)) = calc_ball_centers(a, b, p, radius) else {
+                continue;
+            };
+
+            for ball_center in [front, back] {
+                let angle = self.calc_angle(ball_center);
+
+                if angle < ANGLE_EPSILON || 2. * PI - angle < ANGLE_EPSILON {
+                    continue;
+                }
+
+                if first_hit.is_none_or(|(_, _, best)| angle < best) {
+                    first_hit = Some((i, ball_center, angle));
+                }
+            }
+        }
+
+        first_hit
+    }
+}
@@ -1,99 +1,300 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
+
+use vecmath::{vec3_add, vec3_len, vec3_sub, Vector3};
 
 use crate::edge::Edge;
-use crate::point::Point;
-use crate::utils;
+use crate::half_edge::HalfEdgeMesh;
+use crate::point::Points;
+use crate::utils::{self, decode_cell, MAX_CELL_COORD};
 
 pub struct Grid {
-    pub all_points: Rc<RefCell<Vec<Rc<RefCell<Point>>>>>,
-    pub cells: HashMap<isize, Vec<Rc<RefCell<Point>>>>,
+    pub cells: HashMap<u64, Vec<u32>>,
+    /// Cell code of every point, indexed by point.
+    pub cell_codes: Vec<u64>,
+    /// Minimum corner of the bounding box, cell coordinates are counted from it.
+    pub origin: Vector3<f32>,
     pub radius: f32,
     pub num_cells_per_axis: f32,
     pub bounding_box_size: f32,
-    pub edges: Vec<Rc<RefCell<Edge>>>,
-    pub triangles: Vec<[Rc<RefCell<Point>>; 6]>,
+    pub edges: Vec<Edge>,
+    /// Index in `edges` of the edge between two points, keyed by the smaller point first.
+    pub edge_ids: HashMap<(u32, u32), u32>,
+    /// Triangles built so far, with their adjacency.
+    pub mesh: HalfEdgeMesh,
+    /// Ball radius of the pass that produced each triangle of `mesh`.
+    pub triangle_radii: Vec<f32>,
     pub cell_size: f32,
 }
 
 impl Grid {
-    pub fn new(radius: f32, points: Rc<RefCell<Vec<Rc<RefCell<Point>>>>>) -> Grid {
+    pub fn new(radius: f32, points: &Points) -> Grid {
         let mut grid = Grid {
-            all_points: points,
             cells: HashMap::default(),
+            cell_codes: vec![],
+            origin: [0.0; 3],
             radius,
             num_cells_per_axis: 0.0,
             bounding_box_size: 0.0,
             edges: vec![],
-            triangles: vec![],
+            edge_ids: HashMap::default(),
+            mesh: HalfEdgeMesh::new(),
+            triangle_radii: vec![],
             cell_size: 0.0,
         };
-        grid.init_with_data();
+        grid.init_with_data(points);
+
+        grid
+    }
+
+    /// Returns a grid whose cells hold about `k` points each if the points are spread on a
+    /// surface, for neighbor queries rather than ball pivoting.
+    pub fn for_neighbor_queries(points: &Points, k: usize) -> Grid {
+        let mut grid = Grid::new(0., points);
+
+        // A surface spanning the bounding box holds about `len / size^2` points per unit area.
+        let spacing = grid.bounding_box_size / (points.len().max(1) as f32).sqrt();
+        grid.set_radius(0.5 * spacing * (k.max(1) as f32).sqrt(), points);
 
         grid
     }
 
-    pub fn init_with_data(&mut self) {
-        let (mut min_x, mut max_x, mut min_y, mut max_y, mut min_z, mut max_z) =
-            (0.0f32, 0.0f32, 0.0f32, 0.0f32, 0.0f32, 0.0f32);
-
-        for point in self.all_points.borrow().iter() {
-            min_x = min_x.min(point.borrow().x);
-            min_y = min_y.min(point.borrow().y);
-            min_z = min_z.min(point.borrow().z);
-            max_x = max_x.max(point.borrow().x);
-            max_y = max_y.max(point.borrow().y);
-            max_z = max_z.max(point.borrow().z);
+    pub fn init_with_data(&mut self, points: &Points) {
+        let (mut min, mut max) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+
+        for id in points.ids() {
+            let p = points.coords(id);
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+
+        if points.is_empty() {
+            (min, max) = ([0.0; 3], [0.0; 3]);
         }
 
-        let x = max_x - min_x;
-        let y = max_y - min_y;
-        let z = max_z - min_z;
+        let [x, y, z] = vec3_sub(max, min);
 
+        self.origin = min;
         self.bounding_box_size = x.max(y).max(z);
 
-        self.num_cells_per_axis = self.bounding_box_size / (2. * self.radius);
-        self.cell_size = self.bounding_box_size / self.num_cells_per_axis;
+        // Cells are never smaller than the ball diameter, and grow if needed so that every
+        // cell coordinate fits in a cell code.
+        self.cell_size = (2. * self.radius).max(self.bounding_box_size / MAX_CELL_COORD as f32);
+        self.num_cells_per_axis = self.bounding_box_size / self.cell_size;
 
-        for point in self.all_points.borrow_mut().drain(..) {
-            let x_cell = (point.borrow().x / self.cell_size) as isize * self.cell_size as isize;
-            let y_cell = (point.borrow().y / self.cell_size) as isize * self.cell_size as isize;
-            let z_cell = (point.borrow().z / self.cell_size) as isize * self.cell_size as isize;
+        self.cell_codes = Vec::with_capacity(points.len());
+
+        for id in points.ids() {
+            let (x_cell, y_cell, z_cell) = utils::calc_cell(points.coords(id), self.origin, self.cell_size);
 
             let code = utils::encode_cell(x_cell, y_cell, z_cell);
-            point.borrow_mut().cell_code = Some(code);
+            self.cell_codes.push(code);
+
+            self.cells.entry(code).or_default().push(id);
+        }
+    }
 
-            if self.cells.contains_key(&code) {
-                self.cells.insert(code, vec![]);
+    /// Re-buckets the points for a new ball radius, keeping edges and triangles.
+    pub fn set_radius(&mut self, radius: f32, points: &Points) {
+        self.radius = radius;
+        self.cells.clear();
+
+        self.init_with_data(points);
+    }
+
+    /// Returns the code of the cell of `point` and of its 26 neighboring cells.
+    pub fn neighbor_nodes(&self, point: u32) -> Vec<u64> {
+        let cell_code = self.cell_codes[point as usize];
+        let mut neighbor_nodes = vec![cell_code];
+
+        let (x, y, z) = decode_cell(cell_code);
+
+        for i in -1..2 {
+            for j in -1..2 {
+                for k in -1..2 {
+                    let cell_corner = (x + i, y + j, z + k);
+
+                    if [cell_corner.0, cell_corner.1, cell_corner.2].iter().any(|c| !(0..=MAX_CELL_COORD).contains(c)) {
+                        continue;
+                    }
+
+                    let cell_code = utils::encode_cell(cell_corner.0, cell_corner.1, cell_corner.2);
+                    neighbor_nodes.push(cell_code);
+                }
             }
+        }
+
+        neighbor_nodes
+    }
 
-            self.cells.get_mut(&code).unwrap().push(point);
+    pub fn get_cell_points(&self, cell_code: u64) -> &[u32] {
+        self.cells.get(&cell_code).map_or(&[], |points| points.as_slice())
+    }
+
+    /// Returns the points within distance `r` of `center`.
+    pub fn points_within(&self, points: &Points, center: Vector3<f32>, r: f32) -> Vec<u32> {
+        let min = utils::calc_cell(vec3_sub(center, [r; 3]), self.origin, self.cell_size);
+        let max = utils::calc_cell(vec3_add(center, [r; 3]), self.origin, self.cell_size);
+
+        let mut found = vec![];
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    found.extend(
+                        self.get_cell_points(utils::encode_cell(x, y, z))
+                            .iter()
+                            .filter(|&&p| vec3_len(vec3_sub(points.coords(p), center)) <= r),
+                    );
+                }
+            }
         }
+
+        found
     }
 
-    pub fn get_cell_points(&self, cell_code: isize) -> Vec<Rc<RefCell<Point>>> {
-        let mut points = vec![];
+    /// Returns the `k` points closest to `point` within `max_distance` of it, nearest first,
+    /// `point` itself excluded. Fewer points are returned if there are not enough of them.
+    ///
+    /// Cells are visited in cubic shells of growing size around the cell of `point`, up to
+    /// the shell past `max_distance` or past the last occupied cell.
+    pub fn k_nearest(&self, points: &Points, point: u32, k: usize, max_distance: f32) -> Vec<u32> {
+        if k == 0 {
+            return vec![];
+        }
+
+        let center = points.coords(point);
+        let (cx, cy, cz) = decode_cell(self.cell_codes[point as usize]);
+        let max_coord = (self.num_cells_per_axis.floor() as i64).min(MAX_CELL_COORD);
+        let max_ring = [cx, cy, cz].iter().map(|&c| c.max(max_coord - c)).max().unwrap_or(0);
+        let max_ring = match (max_distance / self.cell_size).ceil() {
+            rings if rings < max_ring as f32 => rings as i64,
+            _ => max_ring,
+        };
+
+        let mut candidates: Vec<(f32, u32)> = vec![];
+
+        for ring in 0..=max_ring {
+            for x in (cx - ring).max(0)..=(cx + ring).min(max_coord) {
+                for y in (cy - ring).max(0)..=(cy + ring).min(max_coord) {
+                    // Inside the shell only its two faces across `z` are left.
+                    let zs = if (x - cx).abs() == ring || (y - cy).abs() == ring {
+                        (cz - ring..=cz + ring).step_by(1)
+                    } else {
+                        (cz - ring..=cz + ring).step_by(2 * ring.max(1) as usize)
+                    };
 
-        if self.cells.contains_key(&cell_code) {
-            let p = self.cells.get(&cell_code).unwrap();
-            points.extend(p.iter().map(|r| r.to_owned()));
+                    for z in zs.filter(|z| (0..=max_coord).contains(z)) {
+                        candidates.extend(
+                            self.get_cell_points(utils::encode_cell(x, y, z))
+                                .iter()
+                                .filter(|&&p| p != point)
+                                .map(|&p| (vec3_len(vec3_sub(points.coords(p), center)), p))
+                                .filter(|&(d, _)| d <= max_distance),
+                        );
+                    }
+                }
+            }
+
+            // Points in the next shells are at least `ring * cell_size` away.
+            if candidates.len() >= k {
+                candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+                if candidates[k - 1].0 <= ring as f32 * self.cell_size {
+                    break;
+                }
+            }
         }
 
-        points
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        candidates.into_iter().take(k).map(|(_, p)| p).collect()
     }
 
-    pub fn add_edge(&mut self, edge: Rc<RefCell<Edge>>) {
+    /// Adds `edge` and returns its index in `edges`.
+    pub fn add_edge(&mut self, edge: Edge) -> u32 {
+        let id = self.edges.len() as u32;
+        self.edge_ids.insert((edge.p1.min(edge.p2), edge.p1.max(edge.p2)), id);
         self.edges.push(edge);
+
+        id
+    }
+
+    /// Returns the index in `edges` of the edge connecting `p1` and `p2`, in either direction.
+    pub fn find_edge(&self, p1: u32, p2: u32) -> Option<u32> {
+        self.edge_ids.get(&(p1.min(p2), p1.max(p2))).copied()
+    }
+
+    /// Makes `to` follow `from` in their front loop.
+    pub fn link_edges(&mut self, from: Option<u32>, to: Option<u32>) {
+        if let Some(from) = from {
+            self.edges[from as usize].next = to;
+        }
+        if let Some(to) = to {
+            self.edges[to as usize].prev = from;
+        }
+    }
+
+    /// Replaces the front edge `edge`, from `a` to `b`, by the edges `e1` from `a` to `k` and
+    /// `e2` from `k` to `b` in its front loop.
+    ///
+    /// An edge flagged as glued already was on the front, from `k` to `a` or from `b` to `k`.
+    /// It is removed with its new opposite instead, which may split its loop in two or merge
+    /// two loops into one.
+    pub fn join(&mut self, edge: u32, (e1, glue_e1): (u32, bool), (e2, glue_e2): (u32, bool)) {
+        let links = |e: u32| (self.edges[e as usize].prev, self.edges[e as usize].next);
+        let (prev, next) = links(edge);
+        let (prev1, next1) = links(e1);
+        let (prev2, next2) = links(e2);
+
+        // Links touching a removed edge are cleared below.
+        match (glue_e1, glue_e2) {
+            (false, false) => {
+                self.link_edges(prev, Some(e1));
+                self.link_edges(Some(e1), Some(e2));
+                self.link_edges(Some(e2), next);
+            }
+            (true, false) => {
+                self.link_edges(prev, next1);
+                self.link_edges(prev1, Some(e2));
+                self.link_edges(Some(e2), next);
+            }
+            (false, true) => {
+                self.link_edges(prev, Some(e1));
+                self.link_edges(Some(e1), next2);
+                self.link_edges(prev2, next);
+            }
+            (true, true) => {
+                self.link_edges(prev, next1);
+                self.link_edges(prev1, next2);
+                self.link_edges(prev2, next);
+            }
+        }
+
+        for (e, is_removed) in [(edge, true), (e1, glue_e1), (e2, glue_e2)] {
+            if is_removed {
+                let e = &mut self.edges[e as usize];
+                (e.prev, e.next) = (None, None);
+            }
+        }
+    }
+
+    /// Returns the edges of the front loop of `edge`, starting with it and following the
+    /// `next` links until the loop closes or a link is missing.
+    pub fn front_loop(&self, edge: u32) -> Vec<u32> {
+        let mut edges = vec![edge];
+
+        while let Some(next) = self.edges[*edges.last().unwrap_or(&edge) as usize].next {
+            if next == edge || edges.len() > self.edges.len() {
+                break;
+            }
+            edges.push(next);
+        }
+
+        edges
     }
 
-    pub fn remove_grid(&mut self, edge: Rc<RefCell<Edge>>) {
-        // let e = *edge.borrow();
-        let idx = self
-            .edges
-            .iter()
-            .position(|x| *x.borrow() == *edge.borrow())
-            .unwrap();
-        self.edges.remove(idx);
+    pub fn add_triangle(&mut self, triangle: [u32; 3], radius: f32) {
+        self.mesh.add_face(triangle);
+        self.triangle_radii.push(radius);
     }
 }
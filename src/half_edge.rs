@@ -0,0 +1,257 @@
+/// Triangle connectivity stored as half-edges.
+///
+/// Half-edge `3 * f + i` of face `f` goes from corner `i` to corner `(i + 1) % 3`, so the
+/// face, next and previous half-edges are computed rather than stored. Each vertex keeps its
+/// outgoing half-edges, which makes edge and neighbor queries cost the degree of a vertex
+/// instead of the size of the mesh. Edges shared by more than two faces or by two faces
+/// wound the same way are supported, their half-edges have no twin.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HalfEdgeMesh {
+    faces: Vec<[u32; 3]>,
+    /// Opposite half-edge of every half-edge, `NO_TWIN` on boundary and non-manifold edges.
+    twins: Vec<u32>,
+    /// Outgoing half-edges of every vertex.
+    outgoing: Vec<Vec<u32>>,
+}
+
+const NO_TWIN: u32 = u32::MAX;
+
+impl HalfEdgeMesh {
+    pub fn new() -> HalfEdgeMesh {
+        HalfEdgeMesh::default()
+    }
+
+    pub fn from_faces(faces: impl IntoIterator<Item = [u32; 3]>) -> HalfEdgeMesh {
+        let mut mesh = HalfEdgeMesh::new();
+        for face in faces {
+            mesh.add_face(face);
+        }
+
+        mesh
+    }
+
+    /// Adds a face, pairing its half-edges with unpaired opposite half-edges, and returns its
+    /// index.
+    pub fn add_face(&mut self, face: [u32; 3]) -> u32 {
+        let f = self.faces.len() as u32;
+        let max_vertex = face.iter().copied().max().unwrap_or(0) as usize;
+
+        if self.outgoing.len() <= max_vertex {
+            self.outgoing.resize(max_vertex + 1, vec![]);
+        }
+
+        self.faces.push(face);
+
+        for i in 0..3 {
+            let h = 3 * f + i;
+            let (origin, dest) = (face[i as usize], face[(i as usize + 1) % 3]);
+
+            let twin = self.outgoing[dest as usize]
+                .iter()
+                .copied()
+                .find(|&g| self.dest(g) == origin && self.twins[g as usize] == NO_TWIN);
+
+            self.twins.push(twin.unwrap_or(NO_TWIN));
+            if let Some(twin) = twin {
+                self.twins[twin as usize] = h;
+            }
+
+            self.outgoing[origin as usize].push(h);
+        }
+
+        f
+    }
+
+    pub fn num_faces(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn faces(&self) -> &[[u32; 3]] {
+        &self.faces
+    }
+
+    pub fn face(&self, f: u32) -> [u32; 3] {
+        self.faces[f as usize]
+    }
+
+    pub fn num_half_edges(&self) -> usize {
+        self.twins.len()
+    }
+
+    pub fn face_of(&self, h: u32) -> u32 {
+        h / 3
+    }
+
+    pub fn origin(&self, h: u32) -> u32 {
+        self.faces[(h / 3) as usize][(h % 3) as usize]
+    }
+
+    pub fn dest(&self, h: u32) -> u32 {
+        self.origin(self.next(h))
+    }
+
+    pub fn next(&self, h: u32) -> u32 {
+        h - h % 3 + (h + 1) % 3
+    }
+
+    pub fn prev(&self, h: u32) -> u32 {
+        h - h % 3 + (h + 2) % 3
+    }
+
+    pub fn twin(&self, h: u32) -> Option<u32> {
+        Some(self.twins[h as usize]).filter(|&t| t != NO_TWIN)
+    }
+
+    /// Returns the half-edges leaving `v`.
+    pub fn outgoing(&self, v: u32) -> &[u32] {
+        self.outgoing.get(v as usize).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the first half-edge from `a` to `b`.
+    pub fn half_edge(&self, a: u32, b: u32) -> Option<u32> {
+        self.outgoing(a).iter().copied().find(|&h| self.dest(h) == b)
+    }
+
+    /// Returns the faces that have `a`-`b` as an edge, whatever their winding.
+    pub fn edge_faces(&self, a: u32, b: u32) -> impl Iterator<Item = u32> + '_ {
+        let forward = self.outgoing(a).iter().filter(move |&&h| self.dest(h) == b);
+        let backward = self.outgoing(b).iter().filter(move |&&h| self.dest(h) == a);
+
+        forward.chain(backward).map(|&h| h / 3)
+    }
+
+    /// Checks that `a`-`b` is an edge of exactly one face.
+    pub fn is_boundary_edge(&self, a: u32, b: u32) -> bool {
+        self.edge_faces(a, b).count() == 1
+    }
+
+    /// Returns the vertices sharing an edge with `v`, without duplicates.
+    pub fn vertex_neighbors(&self, v: u32) -> Vec<u32> {
+        let mut neighbors = Vec::with_capacity(2 * self.outgoing(v).len());
+
+        for &h in self.outgoing(v) {
+            for neighbor in [self.dest(h), self.origin(self.prev(h))] {
+                if !neighbors.contains(&neighbor) {
+                    neighbors.push(neighbor);
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Returns the faces around `v`.
+    pub fn vertex_faces(&self, v: u32) -> impl Iterator<Item = u32> + '_ {
+        self.outgoing(v).iter().map(|&h| h / 3)
+    }
+
+    /// Returns the faces around `v` grouped in fans. Faces of a fan are connected through
+    /// edges of `v` shared by exactly two faces, so a manifold vertex has a single fan.
+    pub fn vertex_fans(&self, v: u32) -> Vec<Vec<u32>> {
+        let mut around = self.vertex_faces(v).collect::<Vec<_>>();
+        around.sort_unstable();
+        around.dedup();
+
+        let mut is_visited = vec![false; around.len()];
+        let mut fans = vec![];
+
+        for start in 0..around.len() {
+            if is_visited[start] {
+                continue;
+            }
+            is_visited[start] = true;
+
+            let mut fan = vec![around[start]];
+            let mut i = 0;
+            while i < fan.len() {
+                let f = fan[i];
+                i += 1;
+
+                for w in self.face(f).into_iter().filter(|&w| w != v) {
+                    let mut shared = self.edge_faces(v, w);
+                    let (Some(a), Some(b), None) = (shared.next(), shared.next(), shared.next()) else { continue };
+                    let other = if a == f { b } else { a };
+
+                    if let Ok(j) = around.binary_search(&other) {
+                        if !is_visited[j] {
+                            is_visited[j] = true;
+                            fan.push(other);
+                        }
+                    }
+                }
+            }
+
+            fans.push(fan);
+        }
+
+        fans
+    }
+
+    /// Returns every edge once, as the first of its half-edges.
+    pub fn edges(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.num_half_edges() as u32).filter(|&h| {
+            let (a, b) = (self.origin(h), self.dest(h));
+            let earlier = |g: u32| g < h;
+
+            !self.outgoing(a).iter().any(|&g| self.dest(g) == b && earlier(g))
+                && !self.outgoing(b).iter().any(|&g| self.dest(g) == a && earlier(g))
+        })
+    }
+
+    /// Checks that the edge of `h` belongs to a single face.
+    pub fn is_boundary_half_edge(&self, h: u32) -> bool {
+        self.twin(h).is_none() && self.is_boundary_edge(self.origin(h), self.dest(h))
+    }
+
+    /// Returns the half-edges of the edges that belong to a single face.
+    pub fn boundary_half_edges(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.num_half_edges() as u32).filter(|&h| self.is_boundary_half_edge(h))
+    }
+
+    /// Returns the holes and outer borders of the mesh, as the vertices met walking along
+    /// each chain of boundary edges. Loops follow the winding of their faces when it is
+    /// consistent. A loop touching itself at a vertex may come out split in two, and chains
+    /// through faces of opposite winding may stay open.
+    pub fn boundary_loops(&self) -> Vec<Vec<u32>> {
+        let mut is_visited = vec![false; self.num_half_edges()];
+        let mut loops = vec![];
+
+        for start in self.boundary_half_edges() {
+            if is_visited[start as usize] {
+                continue;
+            }
+
+            let mut boundary = vec![self.origin(start)];
+            let (mut h, mut v) = (start, self.dest(start));
+
+            loop {
+                is_visited[h as usize] = true;
+
+                // Boundary edges leaving `v` are preferred, entering ones are walked backwards.
+                let outgoing = self.outgoing(v).iter().copied();
+                let next = outgoing.clone()
+                    .find(|&g| !is_visited[g as usize] && self.is_boundary_half_edge(g))
+                    .map(|g| (g, self.dest(g)))
+                    .or_else(|| outgoing
+                        .map(|g| self.prev(g))
+                        .find(|&g| !is_visited[g as usize] && self.is_boundary_half_edge(g))
+                        .map(|g| (g, self.origin(g))));
+
+                match next {
+                    Some((g, w)) if v != boundary[0] => {
+                        boundary.push(v);
+                        (h, v) = (g, w);
+                    }
+                    _ => break,
+                }
+            }
+
+            if v != boundary[0] {
+                boundary.push(v);
+            }
+            loops.push(boundary);
+        }
+
+        loops
+    }
+}
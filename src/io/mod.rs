@@ -0,0 +1,101 @@
+use std::fmt;
+
+use vecmath::{vec3_add, vec3_cross, vec3_dot, vec3_sub};
+
+use crate::mesh::Mesh;
+
+pub mod obj;
+pub mod ply;
+pub mod stl;
+pub mod text;
+
+/// Errors returned by the point cloud and mesh readers and writers.
+#[derive(Debug)]
+pub enum IoError {
+    Io(std::io::Error),
+    /// The header is malformed or uses an unsupported feature.
+    InvalidHeader(String),
+    /// A property the reader needs is not declared.
+    MissingProperty { element: String, property: String },
+    /// The file ends before all the declared data was read.
+    Truncated { element: String, expected: usize, read: usize },
+    /// A value could not be parsed, `line` is 1-based.
+    InvalidValue { line: usize, value: String },
+    /// A line of a text file has fewer columns than the layout needs, `column` is 0-based.
+    MissingColumn { line: usize, column: usize },
+    /// A face refers to a vertex that does not exist.
+    InvalidIndex { index: i64, num_vertices: usize },
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IoError::Io(e) => write!(f, "{e}"),
+            IoError::InvalidHeader(message) => write!(f, "invalid header: {message}"),
+            IoError::MissingProperty { element, property } => {
+                write!(f, "element \"{element}\" has no property \"{property}\"")
+            }
+            IoError::Truncated { element, expected, read } => {
+                write!(f, "file truncated: {read} of {expected} \"{element}\" elements read")
+            }
+            IoError::InvalidValue { line, value } => write!(f, "line {line}: invalid value \"{value}\""),
+            IoError::MissingColumn { line, column } => write!(f, "line {line}: no column {column}"),
+            IoError::InvalidIndex { index, num_vertices } => {
+                write!(f, "face refers to vertex {index} of {num_vertices}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IoError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for IoError {
+    fn from(e: std::io::Error) -> IoError {
+        IoError::Io(e)
+    }
+}
+
+/// Returns the vertices used by the triangles of `mesh`, in increasing order, and the
+/// triangles with their corners renumbered into that list. Triangles are wound
+/// counter-clockwise when seen from the side their vertex normals point to.
+pub(crate) fn compact_mesh(mesh: &Mesh) -> (Vec<u32>, Vec<[u32; 3]>) {
+    let mut is_used = vec![false; mesh.vertices.len()];
+    for &corner in mesh.triangles().iter().flatten() {
+        is_used[corner as usize] = true;
+    }
+
+    let vertices = (0..mesh.vertices.len() as u32).filter(|&p| is_used[p as usize]).collect::<Vec<_>>();
+    let mut new_ids = vec![u32::MAX; mesh.vertices.len()];
+    for (new_id, &p) in vertices.iter().enumerate() {
+        new_ids[p as usize] = new_id as u32;
+    }
+
+    let triangles = mesh
+        .triangles()
+        .iter()
+        .map(|&[a, b, c]| {
+            let [pa, pb, pc] = [a, b, c].map(|p| mesh.vertices[p as usize]);
+            let normal = vec3_cross(vec3_sub(pb, pa), vec3_sub(pc, pa));
+            let vertex_normals = [a, b, c]
+                .map(|p| mesh.normals.get(p as usize).copied().unwrap_or_default())
+                .into_iter()
+                .fold([0.; 3], vec3_add);
+
+            let [a, b, c] = [a, b, c].map(|p| new_ids[p as usize]);
+            if vec3_dot(normal, vertex_normals) < 0. {
+                [a, c, b]
+            } else {
+                [a, b, c]
+            }
+        })
+        .collect();
+
+    (vertices, triangles)
+}
@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::io::{compact_mesh, IoError};
+use crate::mesh::Mesh;
+
+/// Reads a Wavefront OBJ file, see [`read_mesh_from`].
+pub fn read_mesh<P: AsRef<Path>>(path: P) -> Result<Mesh, IoError> {
+    read_mesh_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads the `v`, `vn` and `f` statements of a Wavefront OBJ stream.
+///
+/// Faces with more than three corners are split into a fan of triangles. A vertex gets the
+/// normal its face corners refer to, or a zero normal if none does. Other statements are
+/// ignored and the triangle radii of the returned mesh are empty.
+pub fn read_mesh_from<R: BufRead>(reader: R) -> Result<Mesh, IoError> {
+    let mut mesh = Mesh::default();
+    let mut normals: Vec<[f32; 3]> = vec![];
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = i + 1;
+        let invalid = |value: &str| IoError::InvalidValue { line: line_number, value: value.to_string() };
+
+        let mut words = line.split_whitespace();
+        let parse_vector = |words: &mut std::str::SplitWhitespace| -> Result<[f32; 3], IoError> {
+            let mut v = [0.; 3];
+            for c in v.iter_mut() {
+                let word = words.next().ok_or_else(|| invalid(line.trim()))?;
+                *c = word.parse().map_err(|_| invalid(word))?;
+            }
+            Ok(v)
+        };
+
+        match words.next() {
+            Some("v") => {
+                mesh.vertices.push(parse_vector(&mut words)?);
+                mesh.normals.push([0.; 3]);
+            }
+            Some("vn") => normals.push(parse_vector(&mut words)?),
+            Some("f") => {
+                let mut corners = vec![];
+
+                for word in words {
+                    let mut indices = word.split('/');
+                    let vertex = resolve_index(indices.next().unwrap_or(""), mesh.vertices.len()).ok_or_else(|| invalid(word))?;
+                    let normal = match indices.nth(1) {
+                        Some(index) if !index.is_empty() => Some(resolve_index(index, normals.len()).ok_or_else(|| invalid(word))?),
+                        _ => None,
+                    };
+
+                    if let Some(normal) = normal {
+                        mesh.normals[vertex] = normals[normal];
+                    }
+                    corners.push(vertex as u32);
+                }
+
+                if corners.len() < 3 {
+                    return Err(invalid(line.trim()));
+                }
+                for j in 1..corners.len() - 1 {
+                    mesh.topology.add_face([corners[0], corners[j], corners[j + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(mesh)
+}
+
+/// Turns a 1-based or negative OBJ index into a 0-based index below `len`.
+fn resolve_index(index: &str, len: usize) -> Option<usize> {
+    let index: i64 = index.parse().ok()?;
+    let index = if index < 0 { len as i64 + index } else { index - 1 };
+
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+/// Writes the triangles of `mesh` to a Wavefront OBJ file, see [`write_mesh_to`].
+pub fn write_mesh<P: AsRef<Path>>(path: P, mesh: &Mesh) -> Result<(), IoError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_mesh_to(&mut writer, mesh)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes the triangles of `mesh` as Wavefront OBJ.
+///
+/// Only the vertices used by a triangle are written, each once. Normals are written as `vn`
+/// statements when every written vertex has a non-zero normal. Faces are wound
+/// counter-clockwise around the vertex normals.
+pub fn write_mesh_to<W: Write>(mut writer: W, mesh: &Mesh) -> Result<(), IoError> {
+    let (vertices, triangles) = compact_mesh(mesh);
+
+    let has_normals = vertices
+        .iter()
+        .all(|&p| mesh.normals.get(p as usize).is_some_and(|n| n.iter().any(|&c| c != 0.)));
+
+    writeln!(writer, "# generated by ball-pivoting-rs")?;
+
+    for &p in vertices.iter() {
+        let [x, y, z] = mesh.vertices[p as usize];
+        writeln!(writer, "v {x} {y} {z}")?;
+    }
+    if has_normals {
+        for &p in vertices.iter() {
+            let [nx, ny, nz] = mesh.normals[p as usize];
+            writeln!(writer, "vn {nx} {ny} {nz}")?;
+        }
+    }
+
+    for triangle in triangles.iter() {
+        let [a, b, c] = triangle.map(|p| p + 1);
+
+        if has_normals {
+            writeln!(writer, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        } else {
+            writeln!(writer, "f {a} {b} {c}")?;
+        }
+    }
+
+    Ok(())
+}
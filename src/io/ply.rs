@@ -0,0 +1,558 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::Path;
+
+use crate::half_edge::HalfEdgeMesh;
+use crate::io::{compact_mesh, IoError};
+use crate::mesh::Mesh;
+use crate::point::Points;
+
+/// Most vertices allocated ahead of reading them, the store grows past it while reading.
+const MAX_PREALLOCATED_VERTICES: usize = 1 << 20;
+
+/// Encoding of the data following a PLY header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        Some(match name {
+            "char" | "int8" => ScalarType::I8,
+            "uchar" | "uint8" => ScalarType::U8,
+            "short" | "int16" => ScalarType::I16,
+            "ushort" | "uint16" => ScalarType::U16,
+            "int" | "int32" => ScalarType::I32,
+            "uint" | "uint32" => ScalarType::U32,
+            "float" | "float32" => ScalarType::F32,
+            "double" | "float64" => ScalarType::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, ScalarType::F32 | ScalarType::F64)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum PropertyType {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+#[derive(Clone, Debug)]
+struct Property {
+    name: String,
+    kind: PropertyType,
+}
+
+#[derive(Clone, Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn property(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|p| p.name == name)
+    }
+
+    fn required_property(&self, name: &str) -> Result<usize, IoError> {
+        self.property(name).ok_or_else(|| IoError::MissingProperty {
+            element: self.name.clone(),
+            property: name.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    /// Number of lines of the header, used to report ASCII line numbers.
+    num_lines: usize,
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<Header, IoError> {
+    let mut line = String::new();
+    let mut num_lines = 0;
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+
+    let invalid = |message: String| IoError::InvalidHeader(message);
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("missing \"end_header\"".to_string()));
+        }
+        num_lines += 1;
+
+        let words = line.split_whitespace().collect::<Vec<_>>();
+
+        if num_lines == 1 {
+            if words != ["ply"] {
+                return Err(invalid("file does not start with \"ply\"".to_string()));
+            }
+            continue;
+        }
+
+        match words.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", name, "1.0"] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(invalid(format!("unknown format \"{name}\""))),
+                });
+            }
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| invalid(format!("invalid count of element \"{name}\"")))?;
+                elements.push(Element { name: name.to_string(), count, properties: vec![] });
+            }
+            ["property", "list", count, item, name] => {
+                let kind = match (ScalarType::parse(count), ScalarType::parse(item)) {
+                    (Some(count), Some(item)) => PropertyType::List { count, item },
+                    _ => return Err(invalid(format!("unknown type of property \"{name}\""))),
+                };
+                let element = elements.last_mut().ok_or_else(|| invalid(format!("property \"{name}\" outside of an element")))?;
+                element.properties.push(Property { name: name.to_string(), kind });
+            }
+            ["property", ty, name] => {
+                let ty = ScalarType::parse(ty).ok_or_else(|| invalid(format!("unknown type of property \"{name}\"")))?;
+                let element = elements.last_mut().ok_or_else(|| invalid(format!("property \"{name}\" outside of an element")))?;
+                element.properties.push(Property { name: name.to_string(), kind: PropertyType::Scalar(ty) });
+            }
+            ["end_header"] => break,
+            _ => return Err(invalid(format!("unexpected line \"{}\"", line.trim_end()))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("missing \"format\" line".to_string()))?;
+
+    Ok(Header { format, elements, num_lines })
+}
+
+/// Reads the scalars of the body one by one, whatever the encoding.
+struct ValueReader<R> {
+    reader: R,
+    format: Format,
+    line: String,
+    line_number: usize,
+    position: usize,
+}
+
+impl<R: BufRead> ValueReader<R> {
+    /// Returns the next value, `None` at the end of the file.
+    fn read(&mut self, ty: ScalarType) -> Result<Option<f64>, IoError> {
+        match self.format {
+            Format::Ascii => self.read_ascii(),
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => self.read_binary(ty),
+        }
+    }
+
+    fn read_ascii(&mut self) -> Result<Option<f64>, IoError> {
+        loop {
+            let rest = &self.line[self.position..];
+            let start = rest.len() - rest.trim_start().len();
+            let rest = &rest[start..];
+
+            if !rest.is_empty() {
+                let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let word = &rest[..len];
+                self.position += start + len;
+
+                return word.parse().map(Some).map_err(|_| IoError::InvalidValue {
+                    line: self.line_number,
+                    value: word.to_string(),
+                });
+            }
+
+            self.line.clear();
+            self.position = 0;
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+        }
+    }
+
+    fn read_binary(&mut self, ty: ScalarType) -> Result<Option<f64>, IoError> {
+        let mut bytes = [0u8; 8];
+        let bytes = &mut bytes[..ty.size()];
+
+        match self.reader.read_exact(bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+
+        if self.format == Format::BinaryBigEndian {
+            bytes.reverse();
+        }
+
+        Ok(Some(match ty {
+            ScalarType::I8 => bytes[0] as i8 as f64,
+            ScalarType::U8 => bytes[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            ScalarType::I32 => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ScalarType::U32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ScalarType::F32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            ScalarType::F64 => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+        }))
+    }
+
+    /// Reads one element, stores its scalar properties in `row` and the items of its list
+    /// property, if any, in `list`. Returns false if the file ends before the element.
+    fn read_element(&mut self, element: &Element, row: &mut [f64], list: &mut Vec<f64>) -> Result<bool, IoError> {
+        list.clear();
+
+        for (i, property) in element.properties.iter().enumerate() {
+            match property.kind {
+                PropertyType::Scalar(ty) => match self.read(ty)? {
+                    Some(value) => row[i] = value,
+                    None => return Ok(false),
+                },
+                PropertyType::List { count, item } => {
+                    let Some(len) = self.read(count)? else {
+                        return Ok(false);
+                    };
+                    for _ in 0..len as usize {
+                        match self.read(item)? {
+                            Some(value) => list.push(value),
+                            None => return Ok(false),
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Reads the vertices of a PLY file, see [`read_points_from`].
+pub fn read_points<P: AsRef<Path>>(path: P) -> Result<Points, IoError> {
+    read_points_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads the `vertex` element of an ASCII, binary little-endian or binary big-endian PLY stream.
+///
+/// `x`, `y` and `z` are required. Normals are read from `nx`, `ny` and `nz`, colors from
+/// `red`, `green` and `blue` (floating point colors are scaled from `[0, 1]`), intensities
+/// from `intensity` or `scalar_intensity`. Points get their index in the file as id.
+pub fn read_points_from<R: BufRead>(reader: R) -> Result<Points, IoError> {
+    read_from(reader, false).map(|(points, _)| points)
+}
+
+/// Reads a PLY mesh file, see [`read_mesh_from`].
+pub fn read_mesh<P: AsRef<Path>>(path: P) -> Result<Mesh, IoError> {
+    read_mesh_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads the `vertex` and `face` elements of a PLY stream.
+///
+/// Vertices are read as in [`read_points_from`], vertices without normal get a zero normal.
+/// Faces are read from their `vertex_indices` or `vertex_index` list, faces with more than
+/// three corners are split into a fan of triangles. The triangle radii are empty.
+pub fn read_mesh_from<R: BufRead>(reader: R) -> Result<Mesh, IoError> {
+    let (points, triangles) = read_from(reader, true)?;
+
+    if let Some(&corner) = triangles.iter().flatten().find(|&&p| p < 0 || p as usize >= points.len()) {
+        return Err(IoError::InvalidIndex { index: corner, num_vertices: points.len() });
+    }
+
+    Ok(Mesh {
+        vertices: points.ids().map(|p| points.coords(p)).collect(),
+        normals: points.normals.iter().map(|n| n.unwrap_or_default()).collect(),
+        colors: points.colors,
+        topology: HalfEdgeMesh::from_faces(triangles.into_iter().map(|triangle| triangle.map(|p| p as u32))),
+        triangle_radii: vec![],
+    })
+}
+
+/// Reads the vertices and, if `read_faces` is set, the triangles of a PLY stream. Without
+/// faces, the stream is not read past the `vertex` element. Corners are not checked.
+fn read_from<R: BufRead>(mut reader: R, read_faces: bool) -> Result<(Points, Vec<[i64; 3]>), IoError> {
+    let header = read_header(&mut reader)?;
+    let mut values = ValueReader {
+        reader,
+        format: header.format,
+        line: String::new(),
+        line_number: header.num_lines,
+        position: 0,
+    };
+
+    let mut points = None;
+    let mut triangles = vec![];
+    let mut list = vec![];
+
+    for element in header.elements.iter() {
+        let mut row = vec![0.; element.properties.len()];
+
+        if element.name == "face" && read_faces {
+            if element.property("vertex_indices").or_else(|| element.property("vertex_index")).is_none() {
+                return Err(IoError::MissingProperty {
+                    element: element.name.clone(),
+                    property: "vertex_indices".to_string(),
+                });
+            }
+
+            for read in 0..element.count {
+                if !values.read_element(element, &mut row, &mut list)? {
+                    return Err(truncated(element, read));
+                }
+                for i in 1..list.len().saturating_sub(1) {
+                    triangles.push([list[0], list[i], list[i + 1]].map(|p| p as i64));
+                }
+            }
+            continue;
+        }
+
+        if element.name != "vertex" {
+            for read in 0..element.count {
+                if !values.read_element(element, &mut row, &mut list)? {
+                    return Err(truncated(element, read));
+                }
+            }
+            continue;
+        }
+
+        let x = element.required_property("x")?;
+        let y = element.required_property("y")?;
+        let z = element.required_property("z")?;
+
+        let normal = match ["nx", "ny", "nz"].map(|name| element.property(name)) {
+            [Some(nx), Some(ny), Some(nz)] => Some([nx, ny, nz]),
+            _ => None,
+        };
+        let color = match ["red", "green", "blue"].map(|name| element.property(name)) {
+            [Some(r), Some(g), Some(b)] => Some([r, g, b]),
+            _ => None,
+        };
+        let color_scale = match color.map(|[r, ..]| &element.properties[r].kind) {
+            Some(PropertyType::Scalar(ty)) if ty.is_float() => 255.,
+            _ => 1.,
+        };
+        let intensity = element.property("intensity").or_else(|| element.property("scalar_intensity"));
+
+        // The count comes from the file, a truncated or corrupt one must not allocate it all.
+        let mut vertices = Points::with_capacity(element.count.min(MAX_PREALLOCATED_VERTICES));
+
+        for read in 0..element.count {
+            if !values.read_element(element, &mut row, &mut list)? {
+                return Err(truncated(element, read));
+            }
+
+            let id = vertices.push(
+                row[x] as f32,
+                row[y] as f32,
+                row[z] as f32,
+                normal.map(|n| n.map(|i| row[i] as f32)),
+            );
+
+            if let Some(color) = color {
+                vertices.set_color(id, color.map(|i| (row[i] * color_scale).round().clamp(0., 255.) as u8));
+            }
+            if let Some(intensity) = intensity {
+                vertices.set_intensity(id, row[intensity] as f32);
+            }
+        }
+
+        if !read_faces {
+            return Ok((vertices, triangles));
+        }
+        points = Some(vertices);
+    }
+
+    let points = points.ok_or_else(|| IoError::InvalidHeader("no \"vertex\" element".to_string()))?;
+
+    Ok((points, triangles))
+}
+
+fn truncated(element: &Element, read: usize) -> IoError {
+    IoError::Truncated {
+        element: element.name.clone(),
+        expected: element.count,
+        read,
+    }
+}
+
+/// Writes the triangles of `mesh` to a PLY file, see [`write_mesh_to`].
+pub fn write_mesh<P: AsRef<Path>>(path: P, mesh: &Mesh, format: Format) -> Result<(), IoError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_mesh_to(&mut writer, mesh, format)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes the triangles of `mesh` as PLY in the given encoding.
+///
+/// Only the vertices used by a triangle are written, each once, with their normal and their
+/// color if the mesh has colors. Faces are wound counter-clockwise around the vertex normals.
+pub fn write_mesh_to<W: Write>(mut writer: W, mesh: &Mesh, format: Format) -> Result<(), IoError> {
+    let (vertices, triangles) = compact_mesh(mesh);
+
+    write_header_start(&mut writer, format)?;
+    writeln!(writer, "element vertex {}", vertices.len())?;
+    for name in ["x", "y", "z", "nx", "ny", "nz"] {
+        writeln!(writer, "property float {name}")?;
+    }
+    if mesh.colors.is_some() {
+        for name in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {name}")?;
+        }
+    }
+    writeln!(writer, "element face {}", triangles.len())?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    for &p in vertices.iter() {
+        let [x, y, z] = mesh.vertices[p as usize];
+        let [nx, ny, nz] = mesh.normals.get(p as usize).copied().unwrap_or_default();
+        let color = mesh.colors.as_ref().map_or(&[][..], |colors| &colors[p as usize]);
+
+        write_row(&mut writer, format, &[x, y, z, nx, ny, nz], color)?;
+    }
+
+    for &[a, b, c] in triangles.iter() {
+        match format {
+            Format::Ascii => writeln!(writer, "3 {a} {b} {c}")?,
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+                writer.write_all(&[3])?;
+                for corner in [a, b, c] {
+                    write_bytes(&mut writer, format, (corner as i32).to_le_bytes())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a point cloud to a PLY file, see [`write_points_to`].
+pub fn write_points<P: AsRef<Path>>(path: P, points: &Points, format: Format) -> Result<(), IoError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_points_to(&mut writer, points, format)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes a point cloud as PLY in the given encoding. Normals are written if every point has
+/// one, intensities and colors if the cloud has them.
+pub fn write_points_to<W: Write>(mut writer: W, points: &Points, format: Format) -> Result<(), IoError> {
+    let has_normals = points.normals.iter().all(Option::is_some);
+
+    write_header_start(&mut writer, format)?;
+    writeln!(writer, "element vertex {}", points.len())?;
+    for name in ["x", "y", "z"] {
+        writeln!(writer, "property float {name}")?;
+    }
+    if has_normals {
+        for name in ["nx", "ny", "nz"] {
+            writeln!(writer, "property float {name}")?;
+        }
+    }
+    if points.intensities.is_some() {
+        writeln!(writer, "property float intensity")?;
+    }
+    if points.colors.is_some() {
+        for name in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {name}")?;
+        }
+    }
+    writeln!(writer, "end_header")?;
+
+    let mut values = Vec::with_capacity(7);
+
+    for p in points.ids() {
+        values.clear();
+        values.extend(points.coords(p));
+        if has_normals {
+            values.extend(points.normal(p).unwrap_or_default());
+        }
+        values.extend(points.intensity(p));
+
+        let color = points.color(p);
+        write_row(&mut writer, format, &values, color.as_ref().map_or(&[][..], |c| &c[..]))?;
+    }
+
+    Ok(())
+}
+
+fn write_header_start<W: Write>(writer: &mut W, format: Format) -> Result<(), IoError> {
+    let format_name = match format {
+        Format::Ascii => "ascii",
+        Format::BinaryLittleEndian => "binary_little_endian",
+        Format::BinaryBigEndian => "binary_big_endian",
+    };
+
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {format_name} 1.0")?;
+    writeln!(writer, "comment generated by ball-pivoting-rs")?;
+
+    Ok(())
+}
+
+/// Writes one vertex made of `floats` followed by `bytes`.
+fn write_row<W: Write>(writer: &mut W, format: Format, floats: &[f32], bytes: &[u8]) -> Result<(), IoError> {
+    match format {
+        Format::Ascii => {
+            for (i, value) in floats.iter().enumerate() {
+                write!(writer, "{}{value}", if i == 0 { "" } else { " " })?;
+            }
+            for value in bytes {
+                write!(writer, " {value}")?;
+            }
+            writeln!(writer)?;
+        }
+        Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+            for value in floats {
+                write_bytes(writer, format, value.to_le_bytes())?;
+            }
+            writer.write_all(bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes little-endian `bytes` in the byte order of `format`.
+fn write_bytes<W: Write, const N: usize>(writer: &mut W, format: Format, mut bytes: [u8; N]) -> Result<(), IoError> {
+    if format == Format::BinaryBigEndian {
+        bytes.reverse();
+    }
+    writer.write_all(&bytes)?;
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use vecmath::{vec3_add, vec3_normalized, Vector3};
+
+use crate::geometry::calc_triangle_normal;
+use crate::half_edge::HalfEdgeMesh;
+use crate::io::{compact_mesh, IoError};
+use crate::mesh::Mesh;
+
+/// Encoding of an STL file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Ascii,
+    Binary,
+}
+
+/// Reads an STL file, see [`read_mesh_from`].
+pub fn read_mesh<P: AsRef<Path>>(path: P) -> Result<Mesh, IoError> {
+    read_mesh_from(BufReader::new(File::open(path)?))
+}
+
+/// Reads an ASCII or binary STL stream.
+///
+/// The stream is read as ASCII if it starts with `solid` and its first 84 bytes contain a
+/// `facet` or `endsolid` keyword, as binary otherwise. Corners at the same position are
+/// merged into one vertex, whose normal is the average of the normals of its facets. The
+/// triangle radii of the returned mesh are empty.
+pub fn read_mesh_from<R: BufRead>(mut reader: R) -> Result<Mesh, IoError> {
+    let mut header = vec![];
+    reader.by_ref().take(84).read_to_end(&mut header)?;
+
+    let contains = |keyword: &[u8]| header.windows(keyword.len()).any(|w| w == keyword);
+    let is_ascii = header.starts_with(b"solid") && (contains(b"facet") || contains(b"endsolid"));
+
+    let mut builder = MeshBuilder::default();
+
+    if is_ascii {
+        read_ascii(header.chain(reader), &mut builder)?;
+    } else {
+        if header.len() < 84 {
+            return Err(IoError::InvalidHeader("binary header shorter than 84 bytes".to_string()));
+        }
+        read_binary(&header, reader, &mut builder)?;
+    }
+
+    Ok(builder.finish())
+}
+
+fn read_ascii<R: BufRead>(reader: R, builder: &mut MeshBuilder) -> Result<(), IoError> {
+    let mut corners = vec![];
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let invalid = |value: &str| IoError::InvalidValue { line: i + 1, value: value.to_string() };
+
+        let mut words = line.split_whitespace();
+        if words.next() != Some("vertex") {
+            continue;
+        }
+
+        let mut corner = [0.; 3];
+        for c in corner.iter_mut() {
+            let word = words.next().ok_or_else(|| invalid(line.trim()))?;
+            *c = word.parse().map_err(|_| invalid(word))?;
+        }
+        corners.push(corner);
+
+        if corners.len() == 3 {
+            builder.add_triangle([corners[0], corners[1], corners[2]]);
+            corners.clear();
+        }
+    }
+
+    if !corners.is_empty() {
+        let read = builder.topology.num_faces();
+        return Err(IoError::Truncated { element: "facet".to_string(), expected: read + 1, read });
+    }
+
+    Ok(())
+}
+
+fn read_binary<R: BufRead>(header: &[u8], mut reader: R, builder: &mut MeshBuilder) -> Result<(), IoError> {
+    let count = u32::from_le_bytes(header[80..84].try_into().unwrap()) as usize;
+    let mut facet = [0u8; 50];
+
+    for read in 0..count {
+        match reader.read_exact(&mut facet) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                return Err(IoError::Truncated { element: "facet".to_string(), expected: count, read });
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        // The first 12 bytes are the facet normal, recomputed from the corners.
+        let value = |i: usize| f32::from_le_bytes(facet[12 + 4 * i..16 + 4 * i].try_into().unwrap());
+        let corner = |c: usize| [value(3 * c), value(3 * c + 1), value(3 * c + 2)];
+        builder.add_triangle([corner(0), corner(1), corner(2)]);
+    }
+
+    Ok(())
+}
+
+/// Merges the corners of STL facets into an indexed mesh.
+#[derive(Default)]
+struct MeshBuilder {
+    ids: HashMap<[u32; 3], u32>,
+    vertices: Vec<Vector3<f32>>,
+    normals: Vec<Vector3<f32>>,
+    topology: HalfEdgeMesh,
+}
+
+impl MeshBuilder {
+    fn add_triangle(&mut self, corners: [Vector3<f32>; 3]) {
+        let normal = calc_triangle_normal(corners[0], corners[1], corners[2]);
+
+        let triangle = corners.map(|corner| {
+            let id = *self.ids.entry(corner.map(f32::to_bits)).or_insert_with(|| {
+                self.vertices.push(corner);
+                self.normals.push([0.; 3]);
+                (self.vertices.len() - 1) as u32
+            });
+
+            if normal.iter().all(|c| c.is_finite()) {
+                self.normals[id as usize] = vec3_add(self.normals[id as usize], normal);
+            }
+            id
+        });
+
+        self.topology.add_face(triangle);
+    }
+
+    fn finish(self) -> Mesh {
+        Mesh {
+            vertices: self.vertices,
+            normals: self
+                .normals
+                .into_iter()
+                .map(|n| if n == [0.; 3] { n } else { vec3_normalized(n) })
+                .collect(),
+            colors: None,
+            topology: self.topology,
+            triangle_radii: vec![],
+        }
+    }
+}
+
+/// Writes the triangles of `mesh` to an STL file, see [`write_mesh_to`].
+pub fn write_mesh<P: AsRef<Path>>(path: P, mesh: &Mesh, format: Format) -> Result<(), IoError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_mesh_to(&mut writer, mesh, format)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes the triangles of `mesh` as STL in the given encoding.
+///
+/// Facets are wound counter-clockwise around the vertex normals and their normal is the
+/// normalized cross product of their edges.
+pub fn write_mesh_to<W: Write>(mut writer: W, mesh: &Mesh, format: Format) -> Result<(), IoError> {
+    let (vertices, triangles) = compact_mesh(mesh);
+    let corners = |triangle: &[u32; 3]| triangle.map(|p| mesh.vertices[vertices[p as usize] as usize]);
+
+    match format {
+        Format::Ascii => {
+            writeln!(writer, "solid ball-pivoting-rs")?;
+
+            for triangle in triangles.iter() {
+                let [a, b, c] = corners(triangle);
+                let [nx, ny, nz] = calc_triangle_normal(a, b, c);
+
+                writeln!(writer, "facet normal {nx} {ny} {nz}")?;
+                writeln!(writer, "outer loop")?;
+                for [x, y, z] in [a, b, c] {
+                    writeln!(writer, "vertex {x} {y} {z}")?;
+                }
+                writeln!(writer, "endloop")?;
+                writeln!(writer, "endfacet")?;
+            }
+
+            writeln!(writer, "endsolid ball-pivoting-rs")?;
+        }
+        Format::Binary => {
+            let mut header = [0u8; 80];
+            let name = b"binary STL generated by ball-pivoting-rs";
+            header[..name.len()].copy_from_slice(name);
+
+            writer.write_all(&header)?;
+            writer.write_all(&(triangles.len() as u32).to_le_bytes())?;
+
+            for triangle in triangles.iter() {
+                let [a, b, c] = corners(triangle);
+
+                for value in [calc_triangle_normal(a, b, c), a, b, c].into_iter().flatten() {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                writer.write_all(&[0; 2])?;
+            }
+        }
+    }
+
+    Ok(())
+}
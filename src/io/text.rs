@@ -0,0 +1,124 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::io::IoError;
+use crate::point::Points;
+
+/// Indices, starting at 0, of the columns of a text point cloud.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Columns {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    pub normal: Option<[usize; 3]>,
+    /// Red, green and blue columns, with values from 0 to 255.
+    pub color: Option<[usize; 3]>,
+    pub intensity: Option<usize>,
+}
+
+/// Layout of a text point cloud, one point per line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextOptions {
+    pub columns: Columns,
+    /// Character between the columns, `None` splits on any whitespace.
+    pub delimiter: Option<char>,
+    /// Number of lines skipped at the start of the file, e.g. a CSV header or a PTS point count.
+    pub skip_lines: usize,
+    /// Lines starting with this prefix, after leading whitespace, are ignored.
+    pub comment: Option<String>,
+}
+
+impl Default for TextOptions {
+    /// `x y z` separated by whitespace, `#` starting comments.
+    fn default() -> TextOptions {
+        TextOptions {
+            columns: Columns { x: 0, y: 1, z: 2, normal: None, color: None, intensity: None },
+            delimiter: None,
+            skip_lines: 0,
+            comment: Some("#".to_string()),
+        }
+    }
+}
+
+impl TextOptions {
+    /// `x y z nx ny nz` separated by whitespace.
+    pub fn xyz_normals() -> TextOptions {
+        let mut options = TextOptions::default();
+        options.columns.normal = Some([3, 4, 5]);
+        options
+    }
+
+    /// `x,y,z` with a header line.
+    pub fn csv() -> TextOptions {
+        TextOptions { delimiter: Some(','), skip_lines: 1, ..TextOptions::default() }
+    }
+
+    /// Leica PTS: a point count line, then `x y z intensity r g b`.
+    pub fn pts() -> TextOptions {
+        let mut options = TextOptions { skip_lines: 1, ..TextOptions::default() };
+        options.columns.intensity = Some(3);
+        options.columns.color = Some([4, 5, 6]);
+        options
+    }
+}
+
+/// Reads a text point cloud file, see [`read_points_from`].
+pub fn read_points<P: AsRef<Path>>(path: P, options: &TextOptions) -> Result<Points, IoError> {
+    read_points_from(BufReader::new(File::open(path)?), options)
+}
+
+/// Reads a text point cloud with one point per line, laid out as described by `options`.
+///
+/// The stream is read line by line, extra columns and blank lines are ignored. Points get
+/// their index among the read points as id.
+pub fn read_points_from<R: BufRead>(mut reader: R, options: &TextOptions) -> Result<Points, IoError> {
+    let columns = &options.columns;
+    let mut points = Points::new();
+
+    let mut line = String::new();
+    let mut line_number = 0;
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let content = line.trim();
+        let is_comment = options.comment.as_ref().is_some_and(|prefix| content.starts_with(prefix.as_str()));
+
+        if line_number <= options.skip_lines || content.is_empty() || is_comment {
+            continue;
+        }
+
+        let values = match options.delimiter {
+            Some(delimiter) => content.split(delimiter).map(str::trim).collect::<Vec<_>>(),
+            None => content.split_whitespace().collect(),
+        };
+
+        let value = |column: usize| -> Result<f32, IoError> {
+            let word = values
+                .get(column)
+                .ok_or(IoError::MissingColumn { line: line_number, column })?;
+            word.parse().map_err(|_| IoError::InvalidValue { line: line_number, value: word.to_string() })
+        };
+
+        let normal = match columns.normal {
+            Some([nx, ny, nz]) => Some([value(nx)?, value(ny)?, value(nz)?]),
+            None => None,
+        };
+        let id = points.push(value(columns.x)?, value(columns.y)?, value(columns.z)?, normal);
+
+        if let Some([r, g, b]) = columns.color {
+            let color = [value(r)?, value(g)?, value(b)?];
+            points.set_color(id, color.map(|c| c.round().clamp(0., 255.) as u8));
+        }
+        if let Some(intensity) = columns.intensity {
+            points.set_intensity(id, value(intensity)?);
+        }
+    }
+
+    Ok(points)
+}
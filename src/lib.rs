@@ -2,4 +2,20 @@ pub mod edge;
 pub mod point;
 pub mod utils;
 pub mod grid;
-pub mod bpa;
\ No newline at end of file
+pub mod bpa;
+pub mod geometry;
+pub mod error;
+pub mod mesh;
+pub mod reconstruct;
+pub mod io;
+pub mod normals;
+pub mod radius;
+pub mod filter;
+pub mod half_edge;
+pub mod validation;
+
+pub use error::BpaError;
+pub use half_edge::HalfEdgeMesh;
+pub use mesh::{Mesh, MeshStats};
+pub use reconstruct::{reconstruct, reconstruct_points, reconstruct_with_progress, Progress, ReconstructionOptions};
+pub use validation::{validate, ValidationReport};
\ No newline at end of file
@@ -1,12 +1,438 @@
-use ball_pivoting_rs::{point::Point, edge::Edge, utils::calc_distance_point_to_edge};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use ball_pivoting_rs::filter::{remove_statistical_outliers, voxel_downsample, VoxelRepresentative};
+use ball_pivoting_rs::io::{obj, ply, stl, text, IoError};
+use ball_pivoting_rs::normals::{estimate_normals, orient_normals, LocalShape, Neighborhood};
+use ball_pivoting_rs::point::Points;
+use ball_pivoting_rs::radius::estimate_spacing;
+use ball_pivoting_rs::{reconstruct_points, validate, BpaError, Mesh, Progress, ReconstructionOptions};
 
+const USAGE: &str = "\
+usage: ball-pivoting <command> [options]
 
-fn main() {
-    let p = Point::new(0., 0., 0., 0);
-    let p1 = Point::new(-3., 5., 0., 0);
-    let p2 = Point::new(3., 5., 0., 0);
-    let edge = Edge::new(p1, p2);
+commands:
+  reconstruct <input> <output>   mesh a point cloud with normals
+      --radius <r>               ball radius, repeat for several passes, estimated
+                                 from the point spacing if not given
+      --workers <n>              number of threads (default 1)
+      --min-angle <degrees>      smallest triangle angle (default 20)
+      --max-angle <degrees>      largest triangle angle (default 170)
+      --outlier-std <n>          first drop points whose mean distance to their 20
+                                 nearest neighbors is n standard deviations above average
+      --voxel <size>             keep one point per voxel of this size first
+      --merge <distance>         merge points closer than this distance first
+  estimate-radius <input>        suggest ball radii from the point spacing
+      --samples <n>              number of points sampled (default 1000)
+  normals <input> <output.ply>   estimate the normals of a point cloud
+      --k <n>                    number of neighbors of the fitted planes (default 10)
+      --radius <r>               fit the planes to the points within <r> instead
+      --viewpoint <x,y,z>        orient the normals toward this point, e.g. the scanner
+  stats <mesh>                   print the size and triangle quality of a mesh
+  validate <mesh>                check that a mesh is a consistently wound manifold
 
-    println!("{}", calc_distance_point_to_edge(p, edge));
+options of all commands:
+  --progress                     report progress on stderr
+  --ascii                        write ASCII PLY or STL instead of binary
+
+point clouds are read from .ply, .xyz, .txt, .asc, .csv and .pts files, meshes are
+read from and written to .ply, .obj and .stl files.
+
+exit codes: 0 success, 1 reconstruction failed, 2 invalid arguments, 3 read or write error";
+
+/// Number of neighbors of the statistical outlier removal of `reconstruct`.
+const OUTLIER_NEIGHBORS: usize = 20;
+
+/// Failure of a command, each kind has its own exit code.
+enum CliError {
+    Reconstruction(String),
+    Usage(String),
+    Io(String),
+}
+
+impl CliError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Reconstruction(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::Io(_) => 3,
+        }
+    }
+}
+
+fn io_error(path: &str) -> impl FnOnce(IoError) -> CliError + '_ {
+    move |e| CliError::Io(format!("{path}: {e}"))
+}
+
+/// Command line split into positional arguments, flags and `--name value` options.
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    progress: bool,
+    ascii: bool,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>, valued: &[&str]) -> Result<Args, CliError> {
+        let mut parsed = Args { positional: vec![], options: vec![], progress: false, ascii: false };
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some("progress") => parsed.progress = true,
+                Some("ascii") => parsed.ascii = true,
+                Some(name) if valued.contains(&name) => {
+                    let value = args.next().ok_or_else(|| CliError::Usage(format!("--{name} needs a value")))?;
+                    parsed.options.push((name.to_string(), value));
+                }
+                Some(name) => return Err(CliError::Usage(format!("unknown option --{name}"))),
+                None => parsed.positional.push(arg),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Returns the positional arguments, which must be exactly `names`.
+    fn positional<const N: usize>(&self, names: [&str; N]) -> Result<[&str; N], CliError> {
+        if self.positional.len() != N {
+            return Err(CliError::Usage(format!("expected {}", names.map(|n| format!("<{n}>")).join(" "))));
+        }
+
+        Ok(std::array::from_fn(|i| self.positional[i].as_str()))
+    }
+
+    /// Returns every value of the option `name`.
+    fn values<T: FromStr>(&self, name: &str) -> Result<Vec<T>, CliError> {
+        self.options
+            .iter()
+            .filter(|(option, _)| option == name)
+            .map(|(_, value)| value.parse().map_err(|_| CliError::Usage(format!("invalid value \"{value}\" for --{name}"))))
+            .collect()
+    }
+
+    /// Returns the last value of the option `name`, or `default`.
+    fn value<T: FromStr>(&self, name: &str, default: T) -> Result<T, CliError> {
+        Ok(self.values(name)?.pop().unwrap_or(default))
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+
+    let result = match args.next().as_deref() {
+        Some("reconstruct") => run_reconstruct(args),
+        Some("estimate-radius") => run_estimate_radius(args),
+        Some("normals") => run_normals(args),
+        Some("stats") => run_stats(args),
+        Some("validate") => run_validate(args),
+        Some("-h" | "--help" | "help") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(command) => Err(CliError::Usage(format!("unknown command \"{command}\""))),
+        None => Err(CliError::Usage("no command given".to_string())),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            match &e {
+                CliError::Usage(message) => eprintln!("error: {message}\n\n{USAGE}"),
+                CliError::Reconstruction(message) | CliError::Io(message) => eprintln!("error: {message}"),
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run_reconstruct(args: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(args, &["radius", "workers", "min-angle", "max-angle", "voxel", "outlier-std", "merge"])?;
+    let [input, output] = args.positional(["input", "output"])?;
+
+    let defaults = ReconstructionOptions::default();
+    let options = ReconstructionOptions {
+        radii: args.values("radius")?,
+        num_workers: args.value("workers", defaults.num_workers)?,
+        min_angle: args.value("min-angle", defaults.min_angle)?,
+        max_angle: args.value("max-angle", defaults.max_angle)?,
+        merge_distance: args.values("merge")?.pop(),
+        ..defaults
+    };
+
+    if options.num_workers == 0 {
+        return Err(CliError::Usage("--workers must be at least 1".to_string()));
+    }
+    if let Some(radius) = options.radii.iter().find(|r| !r.is_finite() || **r <= 0.) {
+        return Err(CliError::Usage(format!("--radius must be finite and positive, got {radius}")));
+    }
+    check_mesh_extension(output)?;
+
+    let mut points = read_points(input)?;
+    if args.progress {
+        eprintln!("read {} points from {input}", points.len());
+    }
+
+    if let Some(std_ratio) = args.values("outlier-std")?.pop() {
+        points = points.select(&remove_statistical_outliers(&points, OUTLIER_NEIGHBORS, std_ratio));
+        if args.progress {
+            eprintln!("{} points left after outlier removal", points.len());
+        }
+    }
+
+    if let Some(voxel_size) = args.values("voxel")?.pop() {
+        points = voxel_downsample(&points, voxel_size, VoxelRepresentative::NearestPoint);
+        if args.progress {
+            eprintln!("downsampled to {} points", points.len());
+        }
+    }
+
+    let mut reporter = ProgressReporter::new(args.progress);
+    let mesh = reconstruct_points(points, &options, |progress: &Progress| {
+        reporter.report(|| {
+            format!(
+                "pass {}/{} (radius {}): {:.0}% of the points scanned, {} triangles",
+                progress.pass + 1,
+                progress.num_passes,
+                progress.radius,
+                100. * progress.num_points_scanned as f32 / progress.num_points.max(1) as f32,
+                progress.num_triangles,
+            )
+        })
+    });
+    reporter.finish();
+
+    let mesh = mesh.map_err(|e| match e {
+        BpaError::MissingNormal { .. } => CliError::Reconstruction(format!("{e}, estimate them with `ball-pivoting normals`")),
+        e => CliError::Reconstruction(e.to_string()),
+    })?;
+    write_mesh(output, &mesh, args.ascii)?;
+
+    if args.progress {
+        eprintln!("wrote {} triangles to {output}", mesh.triangles().len());
+    }
+
+    Ok(())
+}
+
+fn run_estimate_radius(args: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(args, &["samples"])?;
+    let [input] = args.positional(["input"])?;
+    let num_samples: usize = args.value("samples", 1000)?;
+
+    let points = read_points(input)?;
+    let stats = estimate_spacing(&points, num_samples)
+        .ok_or_else(|| CliError::Reconstruction(format!("{input}: at least 2 distinct points are needed")))?;
+
+    print_stat("samples", stats.num_samples);
+    print_stat("min spacing", stats.min);
+    print_stat("mean spacing", stats.mean);
+    print_stat("median spacing", stats.median);
+    for q in [0.05, 0.25, 0.75, 0.95] {
+        print_stat(&format!("{:.0}th percentile", 100. * q), stats.percentile(q));
+    }
+    print_stat("max spacing", stats.max);
+
+    let radii = stats.suggested_radii().iter().map(|r| format!("--radius {r}")).collect::<Vec<_>>();
+    print_stat("suggested radii", radii.join(" "));
+
+    Ok(())
+}
+
+fn run_normals(args: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(args, &["k", "radius", "viewpoint"])?;
+    let [input, output] = args.positional(["input", "output"])?;
+    let neighborhood = match args.values("radius")?.pop() {
+        Some(radius) => Neighborhood::Radius(radius),
+        None => Neighborhood::KNearest(args.value("k", 10)?),
+    };
+    let viewpoint = match args.values::<String>("viewpoint")?.pop() {
+        Some(value) => Some(parse_point(&value).ok_or_else(|| CliError::Usage(format!("invalid viewpoint \"{value}\"")))?),
+        None => None,
+    };
+
+    if extension(output).as_deref() != Some("ply") {
+        return Err(CliError::Usage(format!("{output}: normals are written to .ply files")));
+    }
+
+    let mut points = read_points(input)?;
+    if args.progress {
+        eprintln!("estimating the normals of {} points", points.len());
+    }
+
+    let shapes = estimate_normals(&mut points, neighborhood);
+    orient_normals(&mut points, args.value("k", 10)?, viewpoint);
+
+    let mean = |value: fn(&LocalShape) -> f32| shapes.iter().map(value).sum::<f32>() / shapes.len().max(1) as f32;
+    print_stat("mean curvature", mean(|s| s.curvature));
+    print_stat("mean planarity", mean(|s| s.planarity));
+
+    ply::write_points(output, &points, ply_format(args.ascii)).map_err(io_error(output))
+}
+
+fn run_stats(args: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(args, &[])?;
+    let [input] = args.positional(["mesh"])?;
+
+    let stats = read_mesh(input)?.stats();
+
+    print_stat("vertices", stats.num_vertices);
+    print_stat("triangles", stats.num_triangles);
+    print_stat("edges", stats.num_edges);
+    print_stat("boundary edges", stats.num_boundary_edges);
+    print_stat("boundary loops", stats.num_boundary_loops);
+    print_stat("non-manifold edges", stats.num_non_manifold_edges);
+    print_stat("area", stats.area);
+    print_stat("min angle", format!("{:.2}°", stats.min_angle));
+    print_stat("max angle", format!("{:.2}°", stats.max_angle));
+    print_stat("mean aspect ratio", format!("{:.3}", stats.mean_aspect_ratio));
+
+    Ok(())
+}
+
+fn run_validate(args: impl Iterator<Item = String>) -> Result<(), CliError> {
+    let args = Args::parse(args, &[])?;
+    let [input] = args.positional(["mesh"])?;
+
+    let report = validate(&read_mesh(input)?);
+
+    print_stat("vertices", report.num_vertices);
+    print_stat("edges", report.num_edges);
+    print_stat("faces", report.num_faces);
+    print_stat("components", report.num_components);
+    print_stat("boundary loops", report.boundary_loops.len());
+    print_stat("euler number", report.euler_characteristic);
+    print_stat("genus", report.genus.map_or("undefined".to_string(), |genus| genus.to_string()));
+    print_stat("non-manifold edges", report.non_manifold_edges.len());
+    print_stat("non-manifold verts", report.non_manifold_vertices.len());
+    print_stat("flipped edges", report.inconsistent_edges.len());
+    print_stat("duplicate faces", report.duplicate_faces.len());
+    print_stat("degenerate faces", report.degenerate_faces.len());
+
+    let verdict = if report.is_closed() {
+        "closed manifold"
+    } else if report.is_valid() {
+        "manifold with boundary"
+    } else {
+        "invalid"
+    };
+    print_stat("result", verdict);
+
+    Ok(())
+}
+
+fn print_stat(name: &str, value: impl Display) {
+    println!("{:<20}{value}", format!("{name}:"));
+}
+
+/// Parses `x,y,z`.
+fn parse_point(value: &str) -> Option<[f32; 3]> {
+    let coords = value.split(',').map(|c| c.trim().parse().ok()).collect::<Option<Vec<f32>>>()?;
+    coords.try_into().ok()
+}
+
+fn extension(path: &str) -> Option<String> {
+    Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+fn ply_format(ascii: bool) -> ply::Format {
+    if ascii {
+        ply::Format::Ascii
+    } else {
+        ply::Format::BinaryLittleEndian
+    }
+}
+
+/// Reads a point cloud, choosing the reader from the file extension. Plain text files get
+/// normals if their first point has at least six columns.
+fn read_points(path: &str) -> Result<Points, CliError> {
+    let options = match extension(path).as_deref() {
+        Some("ply") => return ply::read_points(path).map_err(io_error(path)),
+        Some("pts") => text::TextOptions::pts(),
+        Some("csv") => text::TextOptions::csv(),
+        Some("xyz" | "txt" | "asc") => {
+            if count_columns(path).map_err(io_error(path))? >= 6 {
+                text::TextOptions::xyz_normals()
+            } else {
+                text::TextOptions::default()
+            }
+        }
+        _ => return Err(CliError::Usage(format!("{path}: unsupported point cloud format"))),
+    };
+
+    text::read_points(path, &options).map_err(io_error(path))
+}
+
+/// Returns the number of columns of the first point of a whitespace separated file.
+fn count_columns(path: &str) -> Result<usize, IoError> {
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if !line.is_empty() && !line.starts_with('#') {
+            return Ok(line.split_whitespace().count());
+        }
+    }
+
+    Ok(0)
+}
+
+fn check_mesh_extension(path: &str) -> Result<(), CliError> {
+    match extension(path).as_deref() {
+        Some("ply" | "obj" | "stl") => Ok(()),
+        _ => Err(CliError::Usage(format!("{path}: unsupported mesh format"))),
+    }
+}
+
+fn read_mesh(path: &str) -> Result<Mesh, CliError> {
+    match extension(path).as_deref() {
+        Some("ply") => ply::read_mesh(path),
+        Some("obj") => obj::read_mesh(path),
+        Some("stl") => stl::read_mesh(path),
+        _ => return Err(CliError::Usage(format!("{path}: unsupported mesh format"))),
+    }
+    .map_err(io_error(path))
+}
+
+fn write_mesh(path: &str, mesh: &Mesh, ascii: bool) -> Result<(), CliError> {
+    match extension(path).as_deref() {
+        Some("ply") => ply::write_mesh(path, mesh, ply_format(ascii)),
+        Some("obj") => obj::write_mesh(path, mesh),
+        Some("stl") => stl::write_mesh(path, mesh, if ascii { stl::Format::Ascii } else { stl::Format::Binary }),
+        _ => return Err(CliError::Usage(format!("{path}: unsupported mesh format"))),
+    }
+    .map_err(io_error(path))
+}
+
+/// Prints progress messages on one stderr line, at most ten times per second.
+struct ProgressReporter {
+    enabled: bool,
+    last: Option<Instant>,
+}
+
+impl ProgressReporter {
+    fn new(enabled: bool) -> ProgressReporter {
+        ProgressReporter { enabled, last: None }
+    }
+
+    fn report(&mut self, message: impl FnOnce() -> String) {
+        if !self.enabled || self.last.is_some_and(|last| last.elapsed() < Duration::from_millis(100)) {
+            return;
+        }
+
+        self.last = Some(Instant::now());
+        eprint!("\r{:<70}", message());
+        let _ = std::io::stderr().flush();
+    }
+
+    fn finish(&mut self) {
+        if self.enabled && self.last.is_some() {
+            eprintln!();
+        }
+    }
 }
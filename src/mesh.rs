@@ -0,0 +1,103 @@
+use vecmath::{vec3_cross, vec3_len, vec3_sub, Vector3};
+
+use crate::half_edge::HalfEdgeMesh;
+
+/// Triangle mesh produced by the reconstruction.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vector3<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+    /// Optional RGB color of every vertex.
+    pub colors: Option<Vec<[u8; 3]>>,
+    /// Triangles, as indices in `vertices` of their corners, with their adjacency.
+    pub topology: HalfEdgeMesh,
+    /// Ball radius of the pass that produced each triangle, empty for meshes read from files.
+    pub triangle_radii: Vec<f32>,
+}
+
+/// Size and triangle quality figures of a [`Mesh`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshStats {
+    /// Number of vertices used by at least one triangle.
+    pub num_vertices: usize,
+    pub num_triangles: usize,
+    pub num_edges: usize,
+    /// Edges of a single triangle.
+    pub num_boundary_edges: usize,
+    /// Closed chains of boundary edges, the holes and outer borders of the surface.
+    pub num_boundary_loops: usize,
+    /// Edges of more than two triangles.
+    pub num_non_manifold_edges: usize,
+    pub area: f32,
+    /// Smallest angle of all triangles, in degrees.
+    pub min_angle: f32,
+    /// Largest angle of all triangles, in degrees.
+    pub max_angle: f32,
+    /// Mean ratio of the circumradius to twice the inradius, 1 for equilateral triangles.
+    pub mean_aspect_ratio: f32,
+}
+
+impl Mesh {
+    /// Returns the indices in `vertices` of the corners of each triangle.
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        self.topology.faces()
+    }
+
+    /// Returns the vertices of each chain of boundary edges, see
+    /// [`HalfEdgeMesh::boundary_loops`].
+    pub fn boundary_loops(&self) -> Vec<Vec<u32>> {
+        self.topology.boundary_loops()
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        let mut is_used = vec![false; self.vertices.len()];
+
+        let mut stats = MeshStats {
+            num_triangles: self.triangles().len(),
+            min_angle: if self.triangles().is_empty() { 0. } else { 180. },
+            ..MeshStats::default()
+        };
+        let mut sum_aspect_ratio = 0.;
+
+        for &[a, b, c] in self.triangles().iter() {
+            for p in [a, b, c] {
+                is_used[p as usize] = true;
+            }
+
+            let [pa, pb, pc] = [a, b, c].map(|p| self.vertices[p as usize]);
+            let [ab, bc, ca] = [(pa, pb), (pb, pc), (pc, pa)].map(|(p, q)| vec3_len(vec3_sub(q, p)));
+            let area = 0.5 * vec3_len(vec3_cross(vec3_sub(pb, pa), vec3_sub(pc, pa)));
+            stats.area += area;
+
+            for (opposite, s1, s2) in [(bc, ab, ca), (ca, ab, bc), (ab, bc, ca)] {
+                let cos = ((s1 * s1 + s2 * s2 - opposite * opposite) / (2. * s1 * s2)).clamp(-1., 1.);
+                let angle = cos.acos().to_degrees();
+                if angle.is_finite() {
+                    stats.min_angle = stats.min_angle.min(angle);
+                    stats.max_angle = stats.max_angle.max(angle);
+                }
+            }
+
+            // R / 2r = abc * s / (8 * area^2), with s the half perimeter.
+            let half_perimeter = 0.5 * (ab + bc + ca);
+            if area > 0. {
+                sum_aspect_ratio += ab * bc * ca * half_perimeter / (8. * area * area);
+            }
+        }
+
+        stats.num_vertices = is_used.iter().filter(|&&used| used).count();
+        for h in self.topology.edges() {
+            let num_faces = self.topology.edge_faces(self.topology.origin(h), self.topology.dest(h)).count();
+
+            stats.num_edges += 1;
+            stats.num_boundary_edges += (num_faces == 1) as usize;
+            stats.num_non_manifold_edges += (num_faces > 2) as usize;
+        }
+        stats.num_boundary_loops = self.boundary_loops().len();
+        if !self.triangles().is_empty() {
+            stats.mean_aspect_ratio = sum_aspect_ratio / self.triangles().len() as f32;
+        }
+
+        stats
+    }
+}
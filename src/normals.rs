@@ -0,0 +1,247 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use vecmath::{vec3_add, vec3_dot, vec3_normalized, vec3_scale, vec3_sub, Vector3};
+
+use crate::grid::Grid;
+use crate::point::Points;
+
+/// Points a plane is fitted to when estimating the normal of a point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighborhood {
+    /// The point and its `k` nearest neighbors.
+    KNearest(usize),
+    /// The points within this distance of the point.
+    Radius(f32),
+}
+
+/// Shape of the neighborhood of a point, from the eigenvalues `l0 <= l1 <= l2` of its
+/// covariance matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LocalShape {
+    /// Surface variation `l0 / (l0 + l1 + l2)`, 0 on a plane and at most 1/3.
+    pub curvature: f32,
+    /// `(l1 - l0) / l2`, close to 1 on a plane and to 0 on a line or an isotropic blob.
+    pub planarity: f32,
+}
+
+/// Sets the normal of every point to the normal of the plane fitted to its neighborhood, and
+/// returns the shape of each neighborhood, indexed by point.
+///
+/// A point that already had a normal keeps its orientation, other normals point away from
+/// the centroid of the cloud. Radius neighborhoods with fewer than three points are replaced
+/// by the point and its two nearest neighbors. Clouds of fewer than three points get `+z`
+/// normals.
+pub fn estimate_normals(points: &mut Points, neighborhood: Neighborhood) -> Vec<LocalShape> {
+    let grid = match neighborhood {
+        Neighborhood::KNearest(k) => Grid::for_neighbor_queries(points, k),
+        Neighborhood::Radius(r) => Grid::new(0.5 * r, points),
+    };
+    let centroid = vec3_scale(
+        points.ids().map(|p| points.coords(p)).fold([0.; 3], vec3_add),
+        1. / points.len().max(1) as f32,
+    );
+
+    let mut shapes = Vec::with_capacity(points.len());
+    let mut neighbors = vec![];
+
+    for p in points.ids() {
+        neighbors.clear();
+        match neighborhood {
+            Neighborhood::KNearest(k) => {
+                neighbors.extend(grid.k_nearest(points, p, k, f32::INFINITY));
+                neighbors.push(p);
+            }
+            Neighborhood::Radius(r) => neighbors.extend(grid.points_within(points, points.coords(p), r)),
+        }
+        if neighbors.len() < 3 {
+            neighbors.clear();
+            neighbors.extend(grid.k_nearest(points, p, 2, f32::INFINITY));
+            neighbors.push(p);
+        }
+
+        let (mut normal, shape) = fit_plane(points, &neighbors).unwrap_or(([0., 0., 1.], LocalShape::default()));
+
+        let reference = points.normal(p).unwrap_or_else(|| vec3_sub(points.coords(p), centroid));
+        if vec3_dot(normal, reference) < 0. {
+            normal = vec3_scale(normal, -1.);
+        }
+
+        points.normals[p as usize] = Some(normal);
+        shapes.push(shape);
+    }
+
+    shapes
+}
+
+/// Returns the unit normal of the least-squares plane through `ids` and the shape of the
+/// points, `None` if they are fewer than three.
+fn fit_plane(points: &Points, ids: &[u32]) -> Option<(Vector3<f32>, LocalShape)> {
+    if ids.len() < 3 {
+        return None;
+    }
+
+    let mean = ids.iter().fold([0f64; 3], |mean, &p| {
+        let c = points.coords(p);
+        [mean[0] + c[0] as f64, mean[1] + c[1] as f64, mean[2] + c[2] as f64]
+    }).map(|c| c / ids.len() as f64);
+
+    let mut covariance = [[0f64; 3]; 3];
+    for &p in ids {
+        let c = points.coords(p);
+        let d = [0, 1, 2].map(|i| c[i] as f64 - mean[i]);
+        for i in 0..3 {
+            for j in 0..3 {
+                covariance[i][j] += d[i] * d[j];
+            }
+        }
+    }
+
+    let (values, vectors) = symmetric_eigen(covariance);
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let [l0, l1, l2] = order.map(|i| values[i].max(0.));
+
+    let normal = vec3_normalized([0, 1, 2].map(|i| vectors[i][order[0]] as f32));
+    let shape = LocalShape {
+        curvature: if l0 + l1 + l2 > 0. { (l0 / (l0 + l1 + l2)) as f32 } else { 0. },
+        planarity: if l2 > 0. { ((l1 - l0) / l2) as f32 } else { 0. },
+    };
+
+    Some((normal, shape))
+}
+
+/// Eigen decomposition of a symmetric 3x3 matrix with the Jacobi method. Returns the
+/// eigenvalues and the matrix whose columns are the matching unit eigenvectors.
+fn symmetric_eigen(mut a: [[f64; 3]; 3]) -> ([f64; 3], [[f64; 3]; 3]) {
+    let mut v = [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+
+    for _ in 0..50 {
+        let off_diagonal = a[0][1].abs() + a[0][2].abs() + a[1][2].abs();
+        if off_diagonal < 1e-12 * (a[0][0].abs() + a[1][1].abs() + a[2][2].abs()).max(f64::MIN_POSITIVE) {
+            break;
+        }
+
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0. {
+                continue;
+            }
+
+            let theta = (a[q][q] - a[p][p]) / (2. * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+            let c = 1. / (t * t + 1.).sqrt();
+            let s = t * c;
+
+            for row in a.iter_mut() {
+                let (akp, akq) = (row[p], row[q]);
+                row[p] = c * akp - s * akq;
+                row[q] = s * akp + c * akq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = [0, 1, 2].map(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = [0, 1, 2].map(|k| s * row_p[k] + c * row_q[k]);
+            for row in v.iter_mut() {
+                let (vkp, vkq) = (row[p], row[q]);
+                row[p] = c * vkp - s * vkq;
+                row[q] = s * vkp + c * vkq;
+            }
+        }
+    }
+
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+/// Flips normals so that neighboring normals agree, by propagating the orientation along a
+/// minimum spanning tree of the `k` nearest neighbors graph weighted by `1 - |n_i . n_j|`
+/// (Hoppe et al. 1992), so that orientation crosses flat regions before curved ones.
+///
+/// Each connected component of the graph starts from one point: with a `viewpoint`, such as
+/// the scanner position, the point closest to it gets a normal facing it, otherwise the
+/// highest point gets a normal pointing up. Points without normal are ignored.
+pub fn orient_normals(points: &mut Points, k: usize, viewpoint: Option<Vector3<f32>>) {
+    let grid = Grid::for_neighbor_queries(points, k);
+    let has_normal = |points: &Points, p: u32| points.normal(p).is_some();
+
+    let mut graph = vec![vec![]; points.len()];
+    for p in points.ids().filter(|&p| has_normal(points, p)) {
+        for q in grid.k_nearest(points, p, k, f32::INFINITY).into_iter().filter(|&q| has_normal(points, q)) {
+            graph[p as usize].push(q);
+            graph[q as usize].push(p);
+        }
+    }
+
+    let weight = |points: &Points, p: u32, q: u32| {
+        1. - vec3_dot(points.normal(p).unwrap(), points.normal(q).unwrap()).abs()
+    };
+    let root_score = |points: &Points, p: u32| match viewpoint {
+        Some(viewpoint) => -vecmath::vec3_len(vec3_sub(points.coords(p), viewpoint)),
+        None => points.coords(p)[2],
+    };
+
+    let mut is_visited = vec![false; points.len()];
+    let mut heap = BinaryHeap::new();
+
+    // Roots are found by flooding each component first, then Prim's algorithm grows its tree.
+    for start in 0..points.len() as u32 {
+        if is_visited[start as usize] || !has_normal(points, start) {
+            continue;
+        }
+
+        let mut component = vec![start];
+        is_visited[start as usize] = true;
+        let mut i = 0;
+        while i < component.len() {
+            for &q in graph[component[i] as usize].iter() {
+                if !is_visited[q as usize] {
+                    is_visited[q as usize] = true;
+                    component.push(q);
+                }
+            }
+            i += 1;
+        }
+
+        let root = component
+            .iter()
+            .copied()
+            .max_by(|&a, &b| root_score(points, a).total_cmp(&root_score(points, b)))
+            .unwrap_or(start);
+        let reference = match viewpoint {
+            Some(viewpoint) => vec3_sub(viewpoint, points.coords(root)),
+            None => [0., 0., 1.],
+        };
+        if vec3_dot(points.normal(root).unwrap(), reference) < 0. {
+            flip(points, root);
+        }
+
+        for &p in component.iter() {
+            is_visited[p as usize] = false;
+        }
+
+        heap.push(Reverse((0u32, root, root)));
+        while let Some(Reverse((_, from, p))) = heap.pop() {
+            if is_visited[p as usize] {
+                continue;
+            }
+            is_visited[p as usize] = true;
+
+            if vec3_dot(points.normal(from).unwrap(), points.normal(p).unwrap()) < 0. {
+                flip(points, p);
+            }
+
+            for &q in graph[p as usize].iter() {
+                if !is_visited[q as usize] {
+                    // Weights are not negative, so their bits sort like them.
+                    let weight = weight(points, p, q).max(0.).to_bits();
+                    heap.push(Reverse((weight, p, q)));
+                }
+            }
+        }
+    }
+}
+
+fn flip(points: &mut Points, p: u32) {
+    if let Some(normal) = points.normals[p as usize].as_mut() {
+        *normal = vec3_scale(*normal, -1.);
+    }
+}
+
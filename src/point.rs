@@ -1,53 +1,131 @@
-use std::{cell::RefCell, rc::Rc};
 use vecmath::Vector3;
 
-use crate::utils::{self, *};
-
-#[derive(PartialEq)]
-pub struct Point {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
-    pub cell_code: Option<isize>,
-    pub normal: Option<Vector3<f32>>,
-    pub id: usize,
-    pub is_used: bool,
+/// Stage of a point in the ball pivoting front.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PointState {
+    /// Vertex of no triangle yet.
+    #[default]
+    Unused,
+    /// Vertex of a front or boundary edge, new triangles can still reach it.
+    Front,
+    /// Vertex whose edges are all shared by two triangles, it is surrounded by the surface.
+    Inner,
 }
 
-impl Point {
-    // TODO cell_node and normal
-    pub fn new(x: f32, y: f32, z: f32, id: usize, normal: Option<Vector3<f32>>) -> Rc<RefCell<Point>> {
-        Rc::new(RefCell::new(Point {
-            x,
-            y,
-            z,
-            cell_code: None,
-            normal,
-            id,
-            is_used: false,
-        }))
-    }
-
-    pub fn neighbor_nodes(&self) -> Vec<isize> {
-        let mut neighbor_nodes = vec![self.cell_code.unwrap()];
-
-        let (x, y, z) = decode_cell(self.cell_code.unwrap());
-
-        for i in -1..2 {
-            for j in -1..2 {
-                for k in -1..2 {
-                    let cell_corner = (x + i, y + j, z + k);
-
-                    if cell_corner.0 < 0 || cell_corner.1 < 0 || cell_corner.2 < 0 {
-                        continue;
-                    }
-
-                    let cell_code = utils::encode_cell(cell_corner.0, cell_corner.1, cell_corner.2);
-                    neighbor_nodes.push(cell_code);
-                }
+/// Struct-of-arrays store of the point cloud. Points are referred to by their `u32` index.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Points {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+    pub normals: Vec<Option<Vector3<f32>>>,
+    /// Optional RGB color of every point.
+    pub colors: Option<Vec<[u8; 3]>>,
+    /// Optional scanner intensity of every point.
+    pub intensities: Option<Vec<f32>>,
+    pub states: Vec<PointState>,
+}
+
+impl Points {
+    pub fn new() -> Points {
+        Points::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Points {
+        Points {
+            x: Vec::with_capacity(capacity),
+            y: Vec::with_capacity(capacity),
+            z: Vec::with_capacity(capacity),
+            normals: Vec::with_capacity(capacity),
+            colors: None,
+            intensities: None,
+            states: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Appends a point and returns its index. If the store has colors or intensities, the
+    /// point gets black and zero, use `set_color` and `set_intensity` to change them.
+    pub fn push(&mut self, x: f32, y: f32, z: f32, normal: Option<Vector3<f32>>) -> u32 {
+        let id = self.x.len() as u32;
+
+        self.x.push(x);
+        self.y.push(y);
+        self.z.push(z);
+        self.normals.push(normal);
+        self.states.push(PointState::Unused);
+
+        if let Some(colors) = self.colors.as_mut() {
+            colors.push([0; 3]);
+        }
+        if let Some(intensities) = self.intensities.as_mut() {
+            intensities.push(0.);
+        }
+
+        id
+    }
+
+    /// Sets the color of `id`, adding black colors to all other points if the store had none.
+    pub fn set_color(&mut self, id: u32, color: [u8; 3]) {
+        let len = self.len();
+        self.colors.get_or_insert_with(|| vec![[0; 3]; len])[id as usize] = color;
+    }
+
+    /// Sets the intensity of `id`, adding zero intensities to all other points if the store had none.
+    pub fn set_intensity(&mut self, id: u32, intensity: f32) {
+        let len = self.len();
+        self.intensities.get_or_insert_with(|| vec![0.; len])[id as usize] = intensity;
+    }
+
+    pub fn color(&self, id: u32) -> Option<[u8; 3]> {
+        self.colors.as_ref().map(|colors| colors[id as usize])
+    }
+
+    pub fn intensity(&self, id: u32) -> Option<f32> {
+        self.intensities.as_ref().map(|intensities| intensities[id as usize])
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn coords(&self, id: u32) -> Vector3<f32> {
+        let i = id as usize;
+        [self.x[i], self.y[i], self.z[i]]
+    }
+
+    pub fn normal(&self, id: u32) -> Option<Vector3<f32>> {
+        self.normals[id as usize]
+    }
+
+    pub fn state(&self, id: u32) -> PointState {
+        self.states[id as usize]
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u32> {
+        0..self.len() as u32
+    }
+
+    /// Returns a new store with the points `ids`, in that order.
+    pub fn select(&self, ids: &[u32]) -> Points {
+        let mut points = Points::with_capacity(ids.len());
+
+        for &id in ids {
+            let [x, y, z] = self.coords(id);
+            let new_id = points.push(x, y, z, self.normal(id));
+            points.states[new_id as usize] = self.states[id as usize];
+
+            if let Some(color) = self.color(id) {
+                points.set_color(new_id, color);
+            }
+            if let Some(intensity) = self.intensity(id) {
+                points.set_intensity(new_id, intensity);
             }
         }
 
-        neighbor_nodes
+        points
     }
 }
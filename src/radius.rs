@@ -0,0 +1,71 @@
+use vecmath::{vec3_len, vec3_sub};
+
+use crate::grid::Grid;
+use crate::point::Points;
+
+const MAX_DUPLICATES: usize = 3;
+
+/// Distribution of the distances from sampled points to their nearest neighbor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpacingStats {
+    pub num_samples: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub median: f32,
+    /// Nearest neighbor distances of the samples, in increasing order.
+    pub distances: Vec<f32>,
+}
+
+impl SpacingStats {
+    /// Returns the distance below which a fraction `q` of the samples lie, `q` in `[0, 1]`.
+    pub fn percentile(&self, q: f32) -> f32 {
+        let i = (q.clamp(0., 1.) * (self.distances.len() - 1) as f32).round() as usize;
+        self.distances[i]
+    }
+
+    /// Returns ball radii of 1, 2 and 4 times the mean spacing, for a multi-radius
+    /// reconstruction that closes the holes left by the smaller balls.
+    pub fn suggested_radii(&self) -> Vec<f32> {
+        [1., 2., 4.].map(|factor| factor * self.mean).to_vec()
+    }
+}
+
+/// Measures the point spacing from the nearest neighbor distances of up to `num_samples`
+/// points spread over the cloud. Duplicates of a point are never its nearest neighbor, points
+/// with more than `MAX_DUPLICATES` of them are left out of the samples. Returns `None` if no
+/// sampled point has a distinct neighbor.
+pub fn estimate_spacing(points: &Points, num_samples: usize) -> Option<SpacingStats> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let grid = Grid::for_neighbor_queries(points, MAX_DUPLICATES + 1);
+    let step = points.len().div_ceil(num_samples.max(1));
+
+    let mut distances = points
+        .ids()
+        .step_by(step)
+        .filter_map(|p| {
+            grid.k_nearest(points, p, MAX_DUPLICATES + 1, f32::INFINITY)
+                .into_iter()
+                .map(|q| vec3_len(vec3_sub(points.coords(p), points.coords(q))))
+                .find(|&d| d > 0.)
+        })
+        .filter(|d| d.is_finite())
+        .collect::<Vec<_>>();
+
+    if distances.is_empty() {
+        return None;
+    }
+    distances.sort_by(f32::total_cmp);
+
+    Some(SpacingStats {
+        num_samples: distances.len(),
+        min: distances[0],
+        max: distances[distances.len() - 1],
+        mean: distances.iter().sum::<f32>() / distances.len() as f32,
+        median: distances[distances.len() / 2],
+        distances,
+    })
+}
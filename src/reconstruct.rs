@@ -0,0 +1,130 @@
+use crate::bpa::BPA;
+use crate::error::BpaError;
+use crate::filter::{merge_duplicates, MergedPoints};
+use crate::half_edge::HalfEdgeMesh;
+use crate::mesh::Mesh;
+use crate::point::Points;
+
+/// Parameters of [`reconstruct`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconstructionOptions {
+    /// Ball radii, one pass is run per radius in increasing order. If empty, radii of 1, 2
+    /// and 4 times the mean point spacing are used, see [`crate::radius::estimate_spacing`].
+    pub radii: Vec<f32>,
+    /// Triangles with an angle below this value, in degrees, are rejected.
+    pub min_angle: f32,
+    /// Triangles with an angle above this value, in degrees, are rejected.
+    pub max_angle: f32,
+    /// Number of nearest neighbors of a point tried as second vertex of a seed triangle.
+    pub seed_neighbors: usize,
+    /// Number of points closest to the first two vertices tried as third vertex of a seed triangle.
+    pub seed_candidates: usize,
+    /// Maximum number of seed triangles grown, `None` grows until no seed is left.
+    /// With a limit, the passes run on a single thread whatever `num_workers`.
+    pub limit_iterations: Option<usize>,
+    pub num_workers: usize,
+    /// If set, points closer than this distance are first merged with
+    /// [`crate::filter::merge_duplicates`].
+    pub merge_distance: Option<f32>,
+}
+
+impl Default for ReconstructionOptions {
+    fn default() -> ReconstructionOptions {
+        ReconstructionOptions {
+            radii: vec![],
+            min_angle: 20.,
+            max_angle: 170.,
+            seed_neighbors: 6,
+            seed_candidates: 5,
+            limit_iterations: None,
+            num_workers: 1,
+            merge_distance: None,
+        }
+    }
+}
+
+/// State of a running reconstruction, see [`reconstruct_with_progress`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// Index of the current radius pass.
+    pub pass: usize,
+    pub num_passes: usize,
+    /// Ball radius of the current pass.
+    pub radius: f32,
+    /// Number of points tried as seed in the current pass.
+    pub num_points_scanned: usize,
+    pub num_points: usize,
+    /// Number of triangles built so far, over all passes.
+    pub num_triangles: usize,
+}
+
+/// Reconstructs a triangle mesh from oriented points with the ball pivoting algorithm.
+///
+/// `normals[i]` is the normal of `points[i]`. The vertices of the returned mesh are the input
+/// points, in the same order.
+pub fn reconstruct(points: &[[f32; 3]], normals: &[[f32; 3]], options: &ReconstructionOptions) -> Result<Mesh, BpaError> {
+    reconstruct_with_progress(points, normals, options, |_| {})
+}
+
+/// Same as [`reconstruct`], calling `progress` as the reconstruction advances.
+pub fn reconstruct_with_progress(
+    points: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    options: &ReconstructionOptions,
+    progress: impl FnMut(&Progress),
+) -> Result<Mesh, BpaError> {
+    if points.len() != normals.len() {
+        return Err(BpaError::LengthMismatch { points: points.len(), normals: normals.len() });
+    }
+
+    let mut cloud = Points::with_capacity(points.len());
+    for (&[x, y, z], &normal) in points.iter().zip(normals) {
+        cloud.push(x, y, z, Some(normal));
+    }
+
+    reconstruct_points(cloud, options, progress)
+}
+
+/// Same as [`reconstruct_with_progress`] for a point store, e.g. one read by [`crate::io`].
+/// Every point needs a normal, the colors of the points become the colors of the vertices.
+///
+/// With a merge distance in the options, the mesh is built from the merged points, then its
+/// triangles use the first point of each group of merged points, so that the vertices are
+/// still the input points.
+pub fn reconstruct_points(
+    points: Points,
+    options: &ReconstructionOptions,
+    mut progress: impl FnMut(&Progress),
+) -> Result<Mesh, BpaError> {
+    let Some(merge_distance) = options.merge_distance else {
+        let mut bpa = BPA::new(points, options.clone())?;
+        bpa.create_mesh(options.limit_iterations, 0, &mut progress)?;
+
+        return Ok(bpa.into_mesh());
+    };
+
+    let MergedPoints { points: merged, remap } = merge_duplicates(&points, merge_distance);
+
+    let mut first_points = vec![u32::MAX; merged.len()];
+    for (p, &new_id) in remap.iter().enumerate().rev() {
+        first_points[new_id as usize] = p as u32;
+    }
+    let first_point = |p: u32| first_points[p as usize];
+
+    let mut bpa = BPA::new(merged, options.clone()).map_err(|e| match e {
+        BpaError::MissingNormal { point_id } => BpaError::MissingNormal { point_id: first_point(point_id) },
+        BpaError::NonFiniteCoordinate { point_id } => BpaError::NonFiniteCoordinate { point_id: first_point(point_id) },
+        e => e,
+    })?;
+    bpa.create_mesh(options.limit_iterations, 0, &mut progress)?;
+
+    let merged_mesh = bpa.into_mesh();
+
+    Ok(Mesh {
+        vertices: points.ids().map(|p| points.coords(p)).collect(),
+        normals: points.normals.iter().map(|n| n.unwrap_or_default()).collect(),
+        colors: points.colors,
+        topology: HalfEdgeMesh::from_faces(merged_mesh.triangles().iter().map(|triangle| triangle.map(first_point))),
+        triangle_radii: merged_mesh.triangle_radii,
+    })
+}
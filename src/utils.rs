@@ -1,61 +1,34 @@
-use std::{cell::RefCell, f32::consts::PI, rc::Rc};
+use std::f32::consts::PI;
 
 use crate::edge::Edge;
-use crate::point::Point;
-use vecmath::{vec3_cross, vec3_dot, vec3_len, Vector3};
+use crate::point::Points;
+use vecmath::{vec3_cross, vec3_dot, vec3_len, vec3_sub, Vector3};
 
-pub fn calc_distance_points(p1: Rc<RefCell<Point>>, p2: Rc<RefCell<Point>>) -> f32 {
-    ((p2.borrow().x - p1.borrow().x).powi(2)
-        + (p2.borrow().y - p1.borrow().y).powi(2)
-        + (p2.borrow().z - p1.borrow().z).powi(2))
-    .sqrt()
+pub fn calc_distance_points(points: &Points, p1: u32, p2: u32) -> f32 {
+    vec3_len(vec3_sub(points.coords(p2), points.coords(p1)))
 }
 
-pub fn calc_distance_point_to_edge(point: Rc<RefCell<Point>>, edge: Rc<RefCell<Edge>>) -> f32 {
-    let v1: Vector3<f32> = [
-        edge.borrow().p1.borrow().x - point.borrow().x,
-        edge.borrow().p1.borrow().y - point.borrow().y,
-        edge.borrow().p1.borrow().z - point.borrow().z,
-    ];
-    let v2: Vector3<f32> = [
-        edge.borrow().p1.borrow().x - edge.borrow().p2.borrow().x,
-        edge.borrow().p1.borrow().y - edge.borrow().p2.borrow().y,
-        edge.borrow().p1.borrow().z - edge.borrow().p2.borrow().z,
-    ];
+pub fn calc_distance_point_to_edge(points: &Points, point: u32, edge: &Edge) -> f32 {
+    let v1: Vector3<f32> = vec3_sub(points.coords(edge.p1), points.coords(point));
+    let v2: Vector3<f32> = vec3_sub(points.coords(edge.p1), points.coords(edge.p2));
     let f = vec3_len(vec3_cross(v1, v2));
     let s = vec3_len(v2);
     f / s
 }
 
-pub fn calc_incircle_radius(
-    p1: Rc<RefCell<Point>>,
-    p2: Rc<RefCell<Point>>,
-    p3: Rc<RefCell<Point>>,
-) -> f32 {
-    let edge_1_len = calc_distance_points(p1.clone(), p2.clone());
-    let edge_2_len = calc_distance_points(p2, p3.clone());
-    let edge_3_len = calc_distance_points(p1, p3);
+pub fn calc_incircle_radius(points: &Points, p1: u32, p2: u32, p3: u32) -> f32 {
+    let edge_1_len = calc_distance_points(points, p1, p2);
+    let edge_2_len = calc_distance_points(points, p2, p3);
+    let edge_3_len = calc_distance_points(points, p1, p3);
 
     let s = (edge_1_len + edge_2_len + edge_3_len) / 2.;
     (((s - edge_1_len) * (s - edge_2_len) * (s - edge_3_len)) / s).sqrt()
 }
 
-pub fn calc_min_max_angle_of_triangle(e1: Rc<RefCell<Edge>>, e2: Rc<RefCell<Edge>>, e3: Rc<RefCell<Edge>>) -> (f32, f32) {
-    let v1 = [
-        e1.borrow().p1.borrow().x - e1.borrow().p2.borrow().x,
-        e1.borrow().p1.borrow().y - e1.borrow().p2.borrow().y,
-        e1.borrow().p1.borrow().z - e1.borrow().p2.borrow().z,
-    ];
-    let v2 = [
-        e2.borrow().p1.borrow().x - e2.borrow().p2.borrow().x,
-        e2.borrow().p1.borrow().y - e2.borrow().p2.borrow().y,
-        e2.borrow().p1.borrow().z - e2.borrow().p2.borrow().z,
-    ];
-    let v3 = [
-        e3.borrow().p1.borrow().x - e3.borrow().p2.borrow().x,
-        e3.borrow().p1.borrow().y - e3.borrow().p2.borrow().y,
-        e3.borrow().p1.borrow().z - e3.borrow().p2.borrow().z,
-    ];
+pub fn calc_min_max_angle_of_triangle(points: &Points, e1: &Edge, e2: &Edge, e3: &Edge) -> (f32, f32) {
+    let v1 = vec3_sub(points.coords(e1.p1), points.coords(e1.p2));
+    let v2 = vec3_sub(points.coords(e2.p1), points.coords(e2.p2));
+    let v3 = vec3_sub(points.coords(e3.p1), points.coords(e3.p2));
 
     let angle1 = (vec3_dot(v1, v2) / (vec3_len(v1) * vec3_len(v2))).acos() * (180. / PI);
     let angle2 = (vec3_dot(v1, v3) / (vec3_len(v1) * vec3_len(v3))).acos() * (180. / PI);
@@ -66,15 +39,28 @@ pub fn calc_min_max_angle_of_triangle(e1: Rc<RefCell<Edge>>, e2: Rc<RefCell<Edge
     (mi, ma)
 }
 
-pub fn encode_cell(x: isize, y: isize, z: isize) -> isize {
-    x | (y << 8) | (z << 16)
-}
+/// Number of bits used per axis in a cell code.
+pub const CELL_BITS: u32 = 21;
+/// Largest cell coordinate that fits in a cell code.
+pub const MAX_CELL_COORD: i64 = (1 << CELL_BITS) - 1;
 
-pub fn decode_cell(code: isize) -> (isize, isize, isize) {
-    let mask_x = 0b000000000000000011111111;
-    let x = code & mask_x;
-    let mask_y = 0b000000001111111100000000;
-    let y = (code & mask_y) >> 8;
-    let z = code >> 16;
+/// Returns the cell coordinates of `point` in a grid of `cell_size` starting at `origin`,
+/// clamped to `[0, MAX_CELL_COORD]`.
+pub fn calc_cell(point: Vector3<f32>, origin: Vector3<f32>, cell_size: f32) -> (i64, i64, i64) {
+    let [x, y, z] = vec3_sub(point, origin).map(|c| ((c / cell_size).floor() as i64).clamp(0, MAX_CELL_COORD));
     (x, y, z)
 }
+
+/// Packs cell coordinates in `[0, MAX_CELL_COORD]` into a single key.
+pub fn encode_cell(x: i64, y: i64, z: i64) -> u64 {
+    debug_assert!([x, y, z].iter().all(|c| (0..=MAX_CELL_COORD).contains(c)));
+    (x as u64) | ((y as u64) << CELL_BITS) | ((z as u64) << (2 * CELL_BITS))
+}
+
+pub fn decode_cell(code: u64) -> (i64, i64, i64) {
+    let mask = MAX_CELL_COORD as u64;
+    let x = code & mask;
+    let y = (code >> CELL_BITS) & mask;
+    let z = code >> (2 * CELL_BITS);
+    (x as i64, y as i64, z as i64)
+}
@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use vecmath::{vec3_cross, vec3_len, vec3_sub};
+
+use crate::mesh::Mesh;
+
+/// Topology problems and invariants of a [`Mesh`], see [`validate`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    /// Number of vertices used by at least one face.
+    pub num_vertices: usize,
+    pub num_edges: usize,
+    pub num_faces: usize,
+    /// Edges shared by more than two faces, as their two vertices.
+    pub non_manifold_edges: Vec<[u32; 2]>,
+    /// Vertices whose faces form more than one fan, such as the common tip of two cones.
+    pub non_manifold_vertices: Vec<u32>,
+    /// Edges whose two faces run along them in the same direction, so that the faces are
+    /// wound in opposite directions.
+    pub inconsistent_edges: Vec<[u32; 2]>,
+    /// Faces with the same vertices as an earlier face, in any order.
+    pub duplicate_faces: Vec<u32>,
+    /// Faces with a repeated vertex or a zero area.
+    pub degenerate_faces: Vec<u32>,
+    /// Vertices of each chain of boundary edges, see [`crate::HalfEdgeMesh::boundary_loops`].
+    pub boundary_loops: Vec<Vec<u32>>,
+    /// Groups of faces connected through their vertices.
+    pub num_components: usize,
+    /// `V - E + F`, 2 for a sphere and 0 for a torus.
+    pub euler_characteristic: i64,
+    /// Number of handles summed over the components, from `V - E + F = 2C - 2g - B`. `None`
+    /// if the mesh is not a consistently wound manifold, where it is not defined.
+    pub genus: Option<usize>,
+}
+
+impl ValidationReport {
+    /// Checks that every edge and vertex has a single fan of faces around it.
+    pub fn is_manifold(&self) -> bool {
+        self.non_manifold_edges.is_empty() && self.non_manifold_vertices.is_empty()
+    }
+
+    /// Checks that the mesh is a consistently wound manifold without duplicate or degenerate
+    /// faces. It may still have boundaries.
+    pub fn is_valid(&self) -> bool {
+        self.is_manifold()
+            && self.inconsistent_edges.is_empty()
+            && self.duplicate_faces.is_empty()
+            && self.degenerate_faces.is_empty()
+    }
+
+    /// Checks that the mesh is valid and has no boundary, so that it encloses a volume.
+    pub fn is_closed(&self) -> bool {
+        self.is_valid() && self.boundary_loops.is_empty()
+    }
+}
+
+/// Checks the topology of `mesh`.
+pub fn validate(mesh: &Mesh) -> ValidationReport {
+    let topology = &mesh.topology;
+    let mut report = ValidationReport { num_faces: topology.num_faces(), ..ValidationReport::default() };
+
+    let mut faces = HashSet::new();
+    for (f, &[a, b, c]) in mesh.triangles().iter().enumerate() {
+        let [pa, pb, pc] = [a, b, c].map(|p| mesh.vertices[p as usize]);
+        let area = 0.5 * vec3_len(vec3_cross(vec3_sub(pb, pa), vec3_sub(pc, pa)));
+
+        if a == b || b == c || c == a || area == 0. {
+            report.degenerate_faces.push(f as u32);
+        }
+
+        let mut sorted = [a, b, c];
+        sorted.sort_unstable();
+        if !faces.insert(sorted) {
+            report.duplicate_faces.push(f as u32);
+        }
+    }
+
+    for h in topology.edges() {
+        let (a, b) = (topology.origin(h), topology.dest(h));
+        let forward = topology.outgoing(a).iter().filter(|&&g| topology.dest(g) == b).count();
+        let backward = topology.outgoing(b).iter().filter(|&&g| topology.dest(g) == a).count();
+
+        report.num_edges += 1;
+        if forward + backward > 2 {
+            report.non_manifold_edges.push([a, b]);
+        } else if forward == 2 || backward == 2 {
+            report.inconsistent_edges.push([a, b]);
+        }
+    }
+
+    let mut vertex_components = DisjointSets::new(mesh.vertices.len());
+    for v in 0..mesh.vertices.len() as u32 {
+        let num_fans = topology.vertex_fans(v).len();
+        if num_fans == 0 {
+            continue;
+        }
+        report.num_vertices += 1;
+
+        for w in topology.vertex_neighbors(v) {
+            vertex_components.union(v, w);
+        }
+
+        if num_fans > 1 {
+            report.non_manifold_vertices.push(v);
+        }
+    }
+
+    report.boundary_loops = topology.boundary_loops();
+    report.num_components = vertex_components.num_sets - (mesh.vertices.len() - report.num_vertices);
+    report.euler_characteristic = report.num_vertices as i64 - report.num_edges as i64 + report.num_faces as i64;
+
+    let twice_genus = 2 * report.num_components as i64 - report.boundary_loops.len() as i64 - report.euler_characteristic;
+    if report.is_manifold() && report.inconsistent_edges.is_empty() && twice_genus >= 0 && twice_genus % 2 == 0 {
+        report.genus = Some((twice_genus / 2) as usize);
+    }
+
+    report
+}
+
+/// Union-find over `0..len`.
+struct DisjointSets {
+    parents: Vec<u32>,
+    num_sets: usize,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> DisjointSets {
+        DisjointSets { parents: (0..len as u32).collect(), num_sets: len }
+    }
+
+    fn find(&mut self, mut i: u32) -> u32 {
+        while self.parents[i as usize] != i {
+            let parent = self.parents[i as usize];
+            self.parents[i as usize] = self.parents[parent as usize];
+            i = parent;
+        }
+
+        i
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[a as usize] = b;
+            self.num_sets -= 1;
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Fixtures shared by the integration tests, each test crate uses a part of them.
+#![allow(dead_code)]
+
+use ball_pivoting_rs::point::Points;
+
+/// Points evenly spread on a sphere of radius 10, with their outward normals.
+pub fn sphere(n: usize) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    let golden_angle = std::f32::consts::PI * (3. - 5f32.sqrt());
+
+    (0..n)
+        .map(|i| {
+            let y = 1. - 2. * i as f32 / (n - 1) as f32;
+            let r = (1. - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            let normal = [theta.cos() * r, y, theta.sin() * r];
+
+            (normal.map(|c| 10. * c), normal)
+        })
+        .unzip()
+}
+
+/// Returns a point store with `coords`, and `normals` if given.
+pub fn points(coords: &[[f32; 3]], normals: Option<&[[f32; 3]]>) -> Points {
+    let mut points = Points::with_capacity(coords.len());
+    for (i, &[x, y, z]) in coords.iter().enumerate() {
+        points.push(x, y, z, normals.map(|normals| normals[i]));
+    }
+
+    points
+}
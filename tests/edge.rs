@@ -0,0 +1,36 @@
+use ball_pivoting_rs::edge::{Edge, EdgeState};
+
+#[test]
+fn edge_goes_from_front_to_boundary_and_back_to_inner() {
+    let mut edge = Edge::new(3, 7);
+    assert_eq!(edge.state, EdgeState::Front);
+
+    edge.add_triangle();
+    assert!(edge.is_front());
+
+    edge.mark_boundary();
+    assert_eq!(edge.state, EdgeState::Boundary);
+    assert!(!edge.is_front() && edge.is_on_front());
+
+    edge.reopen();
+    assert!(edge.is_front());
+
+    edge.add_triangle();
+    assert_eq!(edge.state, EdgeState::Inner);
+    assert!(!edge.is_on_front());
+
+    // Neither marking nor reopening moves an inner edge back on the front.
+    edge.mark_boundary();
+    edge.reopen();
+    assert_eq!(edge.state, EdgeState::Inner);
+}
+
+#[test]
+fn reopening_leaves_front_edges_alone() {
+    let mut edge = Edge::new(0, 1);
+    edge.add_triangle();
+
+    edge.reopen();
+    assert_eq!(edge.state, EdgeState::Front);
+    assert!(edge.connects(1, 0) && edge.contains(1) && !edge.contains(2));
+}
@@ -0,0 +1,168 @@
+use ball_pivoting_rs::filter::{
+    merge_duplicates, remove_radius_outliers, remove_statistical_outliers, voxel_downsample, VoxelRepresentative,
+};
+use ball_pivoting_rs::point::Points;
+use ball_pivoting_rs::{reconstruct_points, validate, BpaError, ReconstructionOptions};
+
+mod common;
+
+fn assert_close(a: [f32; 3], b: [f32; 3]) {
+    assert!(vecmath::vec3_len(vecmath::vec3_sub(a, b)) < 1e-5, "{a:?} != {b:?}");
+}
+
+/// Seven points in three unit voxels, listed in the order of their first points: `b` alone,
+/// `a` with three points and `c` with three points, interleaved.
+fn voxels() -> Points {
+    let mut points = Points::new();
+    let rows = [
+        ([2.5, 0.5, 0.5], [1., 0., 0.], [1, 2, 3], 5.),
+        ([0., 0., 0.], [0., 0., 1.], [0, 0, 0], 1.),
+        ([1.2, 0.2, 0.2], [0., 0., 1.], [100, 100, 100], 0.),
+        ([0.6, 0.3, 0.], [0., 0.6, -0.8], [10, 20, 30], 2.),
+        ([1.9, 0.6, 0.2], [0., 0., -1.], [100, 100, 100], 0.),
+        ([0.3, 0.9, 0.3], [0.6, 0., 0.8], [255, 255, 255], 6.),
+        ([1.3, 0.3, 0.2], [0., 0., 1.], [100, 100, 100], 3.),
+    ];
+
+    for ([x, y, z], normal, color, intensity) in rows {
+        let id = points.push(x, y, z, Some(normal));
+        points.set_color(id, color);
+        points.set_intensity(id, intensity);
+    }
+
+    points
+}
+
+/// A 10 by 10 grid of unit spacing in the plane `z = 0`, then a pair of close outliers far
+/// above it and a lone one beside it, with ids 100 to 102.
+fn plane_with_outliers() -> Points {
+    let mut points = Points::new();
+    for i in 0..100 {
+        points.push((i % 10) as f32, (i / 10) as f32, 0., None);
+    }
+    points.push(50., 50., 50., None);
+    points.push(50.5, 50., 50., None);
+    points.push(-30., 4., 10., None);
+
+    points
+}
+
+#[test]
+fn voxels_are_replaced_by_their_centroid() {
+    let downsampled = voxel_downsample(&voxels(), 1., VoxelRepresentative::Centroid);
+
+    assert_eq!(downsampled.len(), 3);
+    assert_close(downsampled.coords(0), [2.5, 0.5, 0.5]);
+    assert_close(downsampled.coords(1), [0.3, 0.4, 0.1]);
+    assert_close(downsampled.coords(2), [4.4 / 3., 1.1 / 3., 0.2]);
+}
+
+#[test]
+fn voxels_are_replaced_by_the_point_nearest_their_centroid() {
+    let points = voxels();
+    let downsampled = voxel_downsample(&points, 1., VoxelRepresentative::NearestPoint);
+
+    let coords = downsampled.ids().map(|p| downsampled.coords(p)).collect::<Vec<_>>();
+    assert_eq!(coords, [0, 3, 6].map(|p| points.coords(p)));
+}
+
+#[test]
+fn attributes_are_averaged_per_voxel() {
+    let downsampled = voxel_downsample(&voxels(), 1., VoxelRepresentative::Centroid);
+
+    // Normals against the first one of their voxel are flipped before the average.
+    assert_close(downsampled.normal(0).unwrap(), [1., 0., 0.]);
+    assert_close(downsampled.normal(1).unwrap(), vecmath::vec3_normalized([0.6, -0.6, 2.6]));
+    assert_close(downsampled.normal(2).unwrap(), [0., 0., 1.]);
+
+    assert_eq!(downsampled.colors, Some(vec![[1, 2, 3], [88, 92, 95], [100, 100, 100]]));
+    assert_eq!(downsampled.intensities, Some(vec![5., 3., 1.]));
+}
+
+#[test]
+fn non_positive_voxel_sizes_keep_the_cloud() {
+    let points = voxels();
+
+    for voxel_size in [0., -1., f32::NAN] {
+        assert_eq!(voxel_downsample(&points, voxel_size, VoxelRepresentative::Centroid), points);
+    }
+}
+
+#[test]
+fn statistical_outliers_are_removed() {
+    let points = plane_with_outliers();
+
+    assert_eq!(remove_statistical_outliers(&points, 4, 1.), (0..100).collect::<Vec<_>>());
+    assert_eq!(remove_statistical_outliers(&points, 0, 1.), (0..103).collect::<Vec<_>>());
+}
+
+#[test]
+fn radius_outliers_are_removed() {
+    let points = plane_with_outliers();
+
+    // Corners of the grid have two neighbors within the radius, the pair of outliers one each.
+    assert_eq!(remove_radius_outliers(&points, 2, 1.1), (0..100).collect::<Vec<_>>());
+    assert_eq!(remove_radius_outliers(&points, 1, 1.1), (0..102).collect::<Vec<_>>());
+    assert_eq!(remove_radius_outliers(&points, 3, 1.1).len(), 100 - 4);
+}
+
+#[test]
+fn close_points_are_merged_in_id_order() {
+    let mut points = Points::new();
+    for (i, [x, y, z]) in [[0., 0., 0.], [5., 0., 0.], [0.05, 0., 0.], [5., 0.05, 0.], [10., 0., 0.], [10., 0., 0.]].into_iter().enumerate() {
+        let id = points.push(x, y, z, Some(if i == 2 { [0., 0., -1.] } else { [0., 0., 1.] }));
+        points.set_color(id, [10 * i as u8; 3]);
+        points.set_intensity(id, i as f32);
+    }
+
+    let merged = merge_duplicates(&points, 0.1);
+    assert_eq!(merged.remap, vec![0, 1, 0, 1, 2, 2]);
+    assert_eq!(merged.points.len(), 3);
+    assert_close(merged.points.coords(0), [0.025, 0., 0.]);
+    assert_close(merged.points.coords(1), [5., 0.025, 0.]);
+
+    // The normal of point 2 is flipped against the one of point 0 before the average.
+    assert_eq!(merged.points.normal(0), Some([0., 0., 1.]));
+    assert_eq!(merged.points.colors, Some(vec![[10; 3], [20; 3], [45; 3]]));
+    assert_eq!(merged.points.intensities, Some(vec![1., 2., 4.5]));
+
+    let exact = merge_duplicates(&points, 0.);
+    assert_eq!(exact.remap, vec![0, 1, 2, 3, 4, 4]);
+}
+
+#[test]
+fn merged_groups_do_not_chain() {
+    let points = common::points(&[[0., 0., 0.], [0.08, 0., 0.], [0.16, 0., 0.]], None);
+
+    // Point 2 is within the distance of point 1, which already joined the group of point 0.
+    assert_eq!(merge_duplicates(&points, 0.1).remap, vec![0, 0, 1]);
+}
+
+#[test]
+fn reconstruction_of_merged_points_uses_the_input_points() {
+    // Every point of the sphere comes twice, the copy slightly moved.
+    let (coords, normals) = common::sphere(500);
+    let mut points = Points::new();
+    for (&[x, y, z], &normal) in coords.iter().zip(normals.iter()) {
+        points.push(x, y, z, Some(normal));
+        points.push(x + 1e-4, y, z, Some(normal));
+    }
+
+    let options = ReconstructionOptions { radii: vec![2.], merge_distance: Some(1e-3), ..Default::default() };
+    let mesh = reconstruct_points(points, &options, |_| {}).unwrap();
+
+    assert_eq!(mesh.vertices.len(), 1000);
+    assert!(mesh.triangles().iter().flatten().all(|&p| p % 2 == 0));
+    assert!(validate(&mesh).is_closed());
+}
+
+#[test]
+fn missing_normals_are_reported_with_their_input_id() {
+    // Points 0 and 1 merge, so point 3 is the third merged point.
+    let mut points = common::points(&[[0., 0., 0.], [0., 0., 0.], [1., 0., 0.]], Some(&[[0., 0., 1.]; 3]));
+    points.push(0., 1., 0., None);
+
+    let options = ReconstructionOptions { merge_distance: Some(0.), ..Default::default() };
+
+    assert_eq!(reconstruct_points(points, &options, |_| {}).unwrap_err(), BpaError::MissingNormal { point_id: 3 });
+}
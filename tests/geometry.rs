@@ -0,0 +1,97 @@
+use std::f32::consts::PI;
+
+use ball_pivoting_rs::geometry::{calc_ball_center, calc_ball_centers, calc_circumcenter, PivotCircle};
+
+fn assert_close(a: [f32; 3], b: [f32; 3]) {
+    assert!(vecmath::vec3_len(vecmath::vec3_sub(a, b)) < 1e-5, "{a:?} != {b:?}");
+}
+
+/// Ball of radius √2 resting on the edge from (-1, 0, 0) to (1, 0, 0) and on (0, -1, 0), so
+/// its center is (0, 0, 1) and pivoting moves it towards `+y`.
+fn circle() -> PivotCircle {
+    PivotCircle::new([-1., 0., 0.], [1., 0., 0.], [0., -1., 0.], [0., 0., 1.])
+}
+
+#[test]
+fn circumcenter_is_equidistant() {
+    assert_close(calc_circumcenter([0., 0., 0.], [2., 0., 0.], [0., 4., 0.]).unwrap(), [1., 2., 0.]);
+
+    let (a, b, c) = ([1., 0., 0.], [0., 1., 0.], [0., 0., 1.]);
+    assert_close(calc_circumcenter(a, b, c).unwrap(), [1. / 3.; 3]);
+}
+
+#[test]
+fn collinear_points_have_no_circumcenter() {
+    assert_eq!(calc_circumcenter([0., 0., 0.], [1., 1., 1.], [3., 3., 3.]), None);
+    assert_eq!(calc_circumcenter([0., 0., 0.], [1., 0., 0.], [0., 0., 0.]), None);
+    assert_eq!(calc_ball_centers([0., 0., 0.], [1., 0., 0.], [2., 0., 0.], 10.), None);
+}
+
+#[test]
+fn front_ball_center_follows_the_winding() {
+    let (a, b, c) = ([0., 0., 0.], [2., 0., 0.], [0., 2., 0.]);
+    // The circumradius is √2, so a ball of radius √3 stands 1 above or below the plane.
+    let height = 1.;
+
+    let (front, back) = calc_ball_centers(a, b, c, 3f32.sqrt()).unwrap();
+    assert_close(front, [1., 1., height]);
+    assert_close(back, [1., 1., -height]);
+
+    let (front, back) = calc_ball_centers(a, c, b, 3f32.sqrt()).unwrap();
+    assert_close(front, [1., 1., -height]);
+    assert_close(back, [1., 1., height]);
+
+    assert_close(calc_ball_center(a, b, c, 3f32.sqrt(), Some([0.2, 0., -1.])).unwrap(), [1., 1., -height]);
+    assert_close(calc_ball_center(a, c, b, 3f32.sqrt(), None).unwrap(), [1., 1., -height]);
+    assert_eq!(calc_ball_centers(a, b, c, 1.), None);
+}
+
+#[test]
+fn pivot_angle_grows_away_from_the_triangle() {
+    let circle = circle();
+
+    assert!(circle.calc_angle([0., 0., 1.]).abs() < 1e-6);
+    assert!((circle.calc_angle([0., 1., 0.]) - PI / 2.).abs() < 1e-6);
+    assert!((circle.calc_angle([0., 0., -1.]) - PI).abs() < 1e-6);
+    assert!((circle.calc_angle([0., -1., 0.]) - 3. * PI / 2.).abs() < 1e-6);
+
+    // Just behind the start, the angle wraps around to almost a full turn.
+    let angle = circle.calc_angle([0., -1e-3, 1.]);
+    assert!(angle < 2. * PI && 2. * PI - angle < 2e-3, "{angle}");
+}
+
+#[test]
+fn first_hit_is_the_smallest_angle() {
+    let circle = circle();
+    let (a, b, radius) = ([-1., 0., 0.], [1., 0., 0.], 2f32.sqrt());
+
+    // Coplanar points past the edge, the closer to it the earlier the ball meets them. The
+    // last one is too far for the ball to touch it with the edge.
+    let candidates = [[0., 2., 0.], [0., 1.2, 0.], [0., 1.5, 0.], [0., 3., 0.]];
+    let ball_center = |y: f32| {
+        let k = (y * y - 1.) / (2. * y);
+        [0., k, (radius * radius - (1. + k * k)).sqrt()]
+    };
+
+    let (hit, center, angle) = circle.find_first_hit(a, b, radius, &candidates).unwrap();
+    assert_eq!(hit, 1);
+    assert_close(center, ball_center(1.2));
+    assert!((angle - circle.calc_angle(center)).abs() < 1e-6);
+
+    assert_eq!(circle.find_first_hit(a, b, radius, &[candidates[0], candidates[2]]).unwrap().0, 1);
+    assert_eq!(circle.find_first_hit(a, b, radius, &[candidates[0], candidates[3]]).unwrap().0, 0);
+}
+
+#[test]
+fn starting_ball_is_not_a_hit() {
+    let circle = circle();
+    let (a, b, radius) = ([-1., 0., 0.], [1., 0., 0.], 2f32.sqrt());
+
+    // The ball through the opposite point is the starting one, only its mirror image counts.
+    let (hit, center, angle) = circle.find_first_hit(a, b, radius, &[[0., -1., 0.]]).unwrap();
+    assert_eq!(hit, 0);
+    assert_close(center, [0., 0., -1.]);
+    assert!((angle - PI).abs() < 1e-5);
+
+    assert!(circle.find_first_hit(a, b, radius, &[[3., 0., 0.], [0., 5., 0.]]).is_none());
+}
@@ -0,0 +1,183 @@
+use ball_pivoting_rs::edge::Edge;
+use ball_pivoting_rs::grid::Grid;
+use ball_pivoting_rs::point::Points;
+use ball_pivoting_rs::utils::{calc_cell, decode_cell, encode_cell, MAX_CELL_COORD};
+
+mod common;
+
+/// Returns a grid whose front is made of `loops`, each given as its points in order.
+fn front(loops: &[&[u32]]) -> Grid {
+    let mut grid = Grid::new(1., &Points::new());
+
+    for points in loops {
+        let edges = (0..points.len())
+            .map(|i| grid.add_edge(Edge::new(points[i], points[(i + 1) % points.len()])))
+            .collect::<Vec<_>>();
+        for i in 0..edges.len() {
+            grid.link_edges(Some(edges[i]), Some(edges[(i + 1) % edges.len()]));
+        }
+    }
+
+    grid
+}
+
+/// Returns the points of the front loop of `edge`, each edge giving its first point.
+fn loop_points(grid: &Grid, edge: u32) -> Vec<u32> {
+    grid.front_loop(edge).into_iter().map(|e| grid.edges[e as usize].p1).collect()
+}
+
+/// Adds the edges from `a` to `k` and from `k` to `b` for a pivot on `edge`, reusing the
+/// existing ones as glued.
+fn pivot(grid: &mut Grid, edge: u32, k: u32) -> ((u32, bool), (u32, bool)) {
+    let Edge { p1: a, p2: b, .. } = grid.edges[edge as usize];
+    let mut new_edge = |p1, p2| match grid.find_edge(p1, p2) {
+        Some(e) => (e, true),
+        None => (grid.add_edge(Edge::new(p1, p2)), false),
+    };
+
+    (new_edge(a, k), new_edge(k, b))
+}
+
+#[test]
+fn cell_codes_round_trip_past_eight_bits() {
+    for cell in [(0, 0, 0), (255, 256, 257), (1000, 70000, 3), (MAX_CELL_COORD, MAX_CELL_COORD, MAX_CELL_COORD)] {
+        assert_eq!(decode_cell(encode_cell(cell.0, cell.1, cell.2)), cell);
+    }
+
+    assert_ne!(encode_cell(256, 0, 0), encode_cell(0, 1, 0));
+    assert_ne!(encode_cell(0, 256, 0), encode_cell(0, 0, 1));
+}
+
+#[test]
+fn cells_are_counted_from_negative_origin() {
+    let origin = [-10., -5., -1.];
+
+    assert_eq!(calc_cell([-10., -5., -1.], origin, 2.), (0, 0, 0));
+    assert_eq!(calc_cell([-7.5, 0., 3.], origin, 2.), (1, 2, 2));
+    assert_eq!(calc_cell([-0.1, -0.1, -0.1], origin, 2.), (4, 2, 0));
+    assert_ne!(calc_cell([-0.1, 0., 0.], origin, 2.), calc_cell([0.1, 0., 0.], origin, 2.));
+}
+
+#[test]
+fn join_replaces_the_edge_by_two() {
+    let mut grid = front(&[&[0, 1, 2]]);
+
+    let (e1, e2) = pivot(&mut grid, 0, 3);
+    grid.join(0, e1, e2);
+
+    assert_eq!(loop_points(&grid, e1.0), vec![0, 3, 1, 2]);
+    assert_eq!(loop_points(&grid, 2).len(), 4);
+    assert_eq!((grid.edges[0].prev, grid.edges[0].next), (None, None));
+}
+
+#[test]
+fn join_glues_an_adjacent_opposite_edge() {
+    // The pivot on 0-1 reaches 3, whose edge 3-0 precedes it on the front.
+    let mut grid = front(&[&[0, 1, 2, 3]]);
+
+    let (e1, e2) = pivot(&mut grid, 0, 3);
+    assert_eq!((e1, e2.1), ((3, true), false));
+    grid.join(0, e1, e2);
+
+    assert_eq!(loop_points(&grid, e2.0), vec![3, 1, 2]);
+    assert_eq!((grid.edges[3].prev, grid.edges[3].next), (None, None));
+
+    // The pivot on 1-2 reaches 3, whose edge 2-3 follows it on the front.
+    let mut grid = front(&[&[0, 1, 2, 3]]);
+
+    let (e1, e2) = pivot(&mut grid, 1, 3);
+    assert_eq!((e1.1, e2), (false, (2, true)));
+    grid.join(1, e1, e2);
+
+    assert_eq!(loop_points(&grid, e1.0), vec![1, 3, 0]);
+}
+
+#[test]
+fn join_fills_an_ear() {
+    let mut grid = front(&[&[0, 1, 2]]);
+
+    let (e1, e2) = pivot(&mut grid, 0, 2);
+    assert!(e1.1 && e2.1);
+    grid.join(0, e1, e2);
+
+    assert!(grid.edges.iter().all(|e| e.prev.is_none() && e.next.is_none()));
+}
+
+#[test]
+fn join_merges_loops_sharing_a_point() {
+    // Both loops pass through 0, the pivot on 0-1 reaches 3 of the other loop.
+    let mut grid = front(&[&[0, 1, 2], &[3, 0, 4]]);
+
+    let (e1, e2) = pivot(&mut grid, 0, 3);
+    assert_eq!((e1, e2.1), ((3, true), false));
+    grid.join(0, e1, e2);
+
+    assert_eq!(loop_points(&grid, e2.0), vec![3, 1, 2, 0, 4]);
+}
+
+#[test]
+fn join_splits_a_loop_glued_away_from_the_edge() {
+    // The loop pinches at 0, the pivot on 0-1 reaches 2, whose edge 2-0 precedes the other
+    // visit of 0.
+    let mut grid = front(&[&[0, 1, 5, 2, 0, 3, 4]]);
+
+    let (e1, e2) = pivot(&mut grid, 0, 2);
+    assert_eq!((e1, e2.1), ((3, true), false));
+    grid.join(0, e1, e2);
+
+    assert_eq!(loop_points(&grid, e2.0), vec![2, 1, 5]);
+    assert_eq!(loop_points(&grid, grid.find_edge(0, 3).unwrap()), vec![0, 3, 4]);
+}
+
+#[test]
+fn k_nearest_stops_at_max_distance() {
+    let coords = (0..200).map(|i| [(i % 10) as f32, (i / 10 % 5) as f32 * 1.5, (i / 50) as f32 * 2.]).collect::<Vec<_>>();
+    let points = common::points(&coords, None);
+    let grid = Grid::for_neighbor_queries(&points, 6);
+
+    for p in points.ids() {
+        for max_distance in [0.5, 1., 1.6, 2.5, f32::INFINITY] {
+            let distance = |q: u32| vecmath::vec3_len(vecmath::vec3_sub(coords[q as usize], coords[p as usize]));
+            let found = grid.k_nearest(&points, p, 6, max_distance);
+
+            let mut expected = points.ids().filter(|&q| q != p && distance(q) <= max_distance).collect::<Vec<_>>();
+            expected.sort_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+            expected.truncate(6);
+
+            assert_eq!(found.iter().map(|&q| distance(q)).collect::<Vec<_>>(), expected.iter().map(|&q| distance(q)).collect::<Vec<_>>());
+        }
+    }
+}
+
+#[test]
+fn queries_match_brute_force_across_negative_coordinates() {
+    // Points along a noisy line from x = -300 to x = 300, 600 cells long with 1-wide cells.
+    let mut seed = 7u32;
+    let mut random = || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+    };
+    let coords = (0..2000).map(|i| [-300. + 0.3 * i as f32 + random(), 2. * random(), -5. + random()]).collect::<Vec<_>>();
+    let points = common::points(&coords, None);
+    let grid = Grid::new(0.5, &points);
+
+    assert!(grid.num_cells_per_axis > 256.);
+    assert!(grid.origin.iter().all(|&c| c < 0.));
+
+    let distance = |p: [f32; 3], q: u32| vecmath::vec3_len(vecmath::vec3_sub(coords[q as usize], p));
+
+    for p in points.ids().step_by(7) {
+        let center = coords[p as usize];
+
+        for r in [0.4, 1., 2.5] {
+            let mut found = grid.points_within(&points, center, r);
+            found.sort_unstable();
+            assert_eq!(found, points.ids().filter(|&q| distance(center, q) <= r).collect::<Vec<_>>(), "point {p}, radius {r}");
+        }
+
+        let mut expected = points.ids().filter(|&q| q != p).collect::<Vec<_>>();
+        expected.sort_by(|&a, &b| distance(center, a).total_cmp(&distance(center, b)));
+        let distances = |found: &[u32]| found.iter().map(|&q| distance(center, q)).collect::<Vec<_>>();
+        assert_eq!(distances(&grid.k_nearest(&points, p, 8, f32::INFINITY)), distances(&expected[..8]), "point {p}");
+    }
+}
@@ -0,0 +1,109 @@
+use ball_pivoting_rs::HalfEdgeMesh;
+
+/// Returns `values` sorted, to compare results whose order does not matter.
+fn sorted(values: impl IntoIterator<Item = u32>) -> Vec<u32> {
+    let mut values = values.into_iter().collect::<Vec<_>>();
+    values.sort_unstable();
+
+    values
+}
+
+/// Returns the edges of `mesh` as sorted vertex pairs, in increasing order.
+fn edges(mesh: &HalfEdgeMesh) -> Vec<[u32; 2]> {
+    let mut edges = mesh.edges().map(|h| {
+        let (a, b) = (mesh.origin(h), mesh.dest(h));
+        [a.min(b), a.max(b)]
+    }).collect::<Vec<_>>();
+    edges.sort_unstable();
+
+    edges
+}
+
+#[test]
+fn opposite_half_edges_are_twins() {
+    // The square 0-1-2-3 split along 0-2.
+    let mut mesh = HalfEdgeMesh::new();
+    assert_eq!(mesh.add_face([0, 1, 2]), 0);
+    assert_eq!(mesh.add_face([0, 2, 3]), 1);
+
+    assert_eq!(mesh.num_half_edges(), 6);
+    assert_eq!((mesh.origin(2), mesh.dest(2)), (2, 0));
+    assert_eq!((mesh.next(2), mesh.prev(2), mesh.face_of(2)), (0, 1, 0));
+    assert_eq!((mesh.twin(2), mesh.twin(3)), (Some(3), Some(2)));
+    assert!([0, 1, 4, 5].iter().all(|&h| mesh.twin(h).is_none()));
+
+    assert_eq!(mesh.half_edge(0, 2), Some(3));
+    assert_eq!(mesh.half_edge(1, 3), None);
+}
+
+#[test]
+fn adjacency_queries_follow_the_faces() {
+    let mesh = HalfEdgeMesh::from_faces([[0, 1, 2], [0, 2, 3]]);
+
+    assert_eq!(sorted(mesh.edge_faces(0, 2)), vec![0, 1]);
+    assert_eq!(sorted(mesh.edge_faces(2, 0)), vec![0, 1]);
+    assert_eq!(sorted(mesh.edge_faces(3, 2)), vec![1]);
+    assert_eq!(mesh.edge_faces(1, 3).count(), 0);
+
+    assert_eq!(sorted(mesh.vertex_neighbors(0)), vec![1, 2, 3]);
+    assert_eq!(sorted(mesh.vertex_neighbors(1)), vec![0, 2]);
+    assert_eq!(sorted(mesh.vertex_faces(2)), vec![0, 1]);
+    assert!(mesh.vertex_neighbors(7).is_empty());
+
+    assert_eq!(edges(&mesh), vec![[0, 1], [0, 2], [0, 3], [1, 2], [2, 3]]);
+    assert_eq!(sorted(mesh.boundary_half_edges()), vec![0, 1, 4, 5]);
+    assert!(mesh.is_boundary_edge(3, 0) && !mesh.is_boundary_edge(0, 2));
+}
+
+#[test]
+fn edges_of_three_faces_have_no_twin() {
+    let mesh = HalfEdgeMesh::from_faces([[0, 1, 2], [1, 0, 3], [0, 1, 4]]);
+
+    // The second face pairs with the first one, the third one is left alone.
+    assert_eq!((mesh.twin(0), mesh.twin(3)), (Some(3), Some(0)));
+    assert_eq!(mesh.twin(6), None);
+
+    assert_eq!(sorted(mesh.edge_faces(0, 1)), vec![0, 1, 2]);
+    assert!(!mesh.is_boundary_edge(0, 1));
+    assert_eq!(edges(&mesh), vec![[0, 1], [0, 2], [0, 3], [0, 4], [1, 2], [1, 3], [1, 4]]);
+    assert!(mesh.boundary_half_edges().all(|h| mesh.origin(h).max(mesh.dest(h)) >= 2));
+    assert_eq!(mesh.boundary_half_edges().count(), 6);
+}
+
+#[test]
+fn edges_of_faces_wound_the_same_way_have_no_twin() {
+    let mesh = HalfEdgeMesh::from_faces([[0, 1, 2], [0, 1, 3]]);
+
+    assert_eq!((mesh.twin(0), mesh.twin(3)), (None, None));
+    assert_eq!(sorted(mesh.edge_faces(1, 0)), vec![0, 1]);
+    assert!(!mesh.is_boundary_half_edge(0) && !mesh.is_boundary_half_edge(3));
+
+    assert_eq!(edges(&mesh), vec![[0, 1], [0, 2], [0, 3], [1, 2], [1, 3]]);
+    assert_eq!(sorted(mesh.boundary_half_edges()), vec![1, 2, 4, 5]);
+    assert_eq!(sorted(mesh.vertex_neighbors(1)), vec![0, 2, 3]);
+}
+
+/// Returns `boundary` rotated to start at its smallest vertex.
+fn from_smallest(mut boundary: Vec<u32>) -> Vec<u32> {
+    let start = (0..boundary.len()).min_by_key(|&i| boundary[i]).unwrap_or(0);
+    boundary.rotate_left(start);
+
+    boundary
+}
+
+#[test]
+fn square_with_a_hole_has_two_boundary_loops() {
+    // A 4 by 4 grid of vertices, vertex `4 * y + x` at `(x, y)`, without its center cell.
+    let v = |x: u32, y: u32| 4 * y + x;
+    let faces = (0..3)
+        .flat_map(|y| (0..3).map(move |x| (x, y)))
+        .filter(|&cell| cell != (1, 1))
+        .flat_map(|(x, y)| [[v(x, y), v(x + 1, y), v(x + 1, y + 1)], [v(x, y), v(x + 1, y + 1), v(x, y + 1)]]);
+    let mesh = HalfEdgeMesh::from_faces(faces);
+
+    let mut loops = mesh.boundary_loops().into_iter().map(from_smallest).collect::<Vec<_>>();
+    loops.sort();
+
+    // The outer loop follows the faces counter-clockwise, the hole goes the other way.
+    assert_eq!(loops, vec![vec![0, 1, 2, 3, 7, 11, 15, 14, 13, 12, 8, 4], vec![5, 9, 10, 6]]);
+}
@@ -0,0 +1,49 @@
+use ball_pivoting_rs::io::{obj, stl};
+use ball_pivoting_rs::{reconstruct, Mesh, ReconstructionOptions};
+
+mod common;
+
+use common::sphere;
+
+fn sphere_mesh() -> Mesh {
+    let (points, normals) = sphere(500);
+    let options = ReconstructionOptions { radii: vec![2.], ..Default::default() };
+
+    reconstruct(&points, &normals, &options).unwrap()
+}
+
+#[test]
+fn obj_round_trip_preserves_counts() {
+    let mesh = sphere_mesh();
+
+    let mut buffer = vec![];
+    obj::write_mesh_to(&mut buffer, &mesh).unwrap();
+    let read = obj::read_mesh_from(&buffer[..]).unwrap();
+
+    assert_eq!(read.vertices.len(), mesh.vertices.len());
+    assert_eq!(read.triangles().len(), mesh.triangles().len());
+    assert!(read.normals.iter().all(|n| n.iter().any(|&c| c != 0.)));
+}
+
+#[test]
+fn stl_round_trip_preserves_counts() {
+    let mesh = sphere_mesh();
+
+    for format in [stl::Format::Ascii, stl::Format::Binary] {
+        let mut buffer = vec![];
+        stl::write_mesh_to(&mut buffer, &mesh, format).unwrap();
+        let read = stl::read_mesh_from(&buffer[..]).unwrap();
+
+        assert_eq!(read.vertices.len(), mesh.vertices.len(), "{format:?}");
+        assert_eq!(read.triangles().len(), mesh.triangles().len(), "{format:?}");
+    }
+}
+
+#[test]
+fn truncated_binary_stl_is_an_error() {
+    let mut buffer = vec![];
+    stl::write_mesh_to(&mut buffer, &sphere_mesh(), stl::Format::Binary).unwrap();
+    buffer.truncate(buffer.len() - 10);
+
+    assert!(matches!(stl::read_mesh_from(&buffer[..]), Err(ball_pivoting_rs::io::IoError::Truncated { .. })));
+}
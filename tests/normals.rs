@@ -0,0 +1,80 @@
+use ball_pivoting_rs::normals::{estimate_normals, orient_normals, Neighborhood};
+use ball_pivoting_rs::point::Points;
+
+mod common;
+
+/// Points of [`common::sphere`] without normals, with their analytic normals.
+fn sphere(n: usize) -> (Points, Vec<[f32; 3]>) {
+    let (coords, normals) = common::sphere(n);
+
+    (common::points(&coords, None), normals)
+}
+
+/// Points on a jittered grid of the plane through the origin with normal `normal`.
+fn plane(normal: [f32; 3], size: usize) -> Points {
+    let u = vecmath::vec3_normalized(vecmath::vec3_cross(normal, [1., 0., 0.]));
+    let v = vecmath::vec3_cross(normal, u);
+    let mut points = Points::new();
+
+    for i in 0..size {
+        for j in 0..size {
+            let (a, b) = (i as f32 + 0.3 * ((i * 7 + j * 3) % 5) as f32 / 5., j as f32 + 0.3 * ((i * 3 + j * 11) % 7) as f32 / 7.);
+            let [x, y, z] = [0, 1, 2].map(|k| a * u[k] + b * v[k]);
+            points.push(x, y, z, None);
+        }
+    }
+
+    points
+}
+
+fn angle(a: [f32; 3], b: [f32; 3]) -> f32 {
+    vecmath::vec3_dot(a, b).clamp(-1., 1.).acos().to_degrees()
+}
+
+#[test]
+fn sphere_normals_are_within_a_degree() {
+    let (mut points, normals) = sphere(20000);
+
+    for neighborhood in [Neighborhood::KNearest(10), Neighborhood::Radius(0.6)] {
+        let shapes = estimate_normals(&mut points, neighborhood);
+
+        for p in points.ids() {
+            let angle = angle(points.normal(p).unwrap(), normals[p as usize]);
+            assert!(angle < 1., "{neighborhood:?}: point {p} is off by {angle} degrees");
+        }
+        assert!(shapes.iter().all(|s| s.curvature < 0.01));
+    }
+}
+
+#[test]
+fn plane_normals_are_within_a_degree() {
+    let normal = vecmath::vec3_normalized([1., 2., 3.]);
+    let mut points = plane(normal, 60);
+
+    let shapes = estimate_normals(&mut points, Neighborhood::KNearest(8));
+
+    for p in points.ids() {
+        // Orientation is arbitrary on a plane, only the direction is checked.
+        let angle = angle(points.normal(p).unwrap(), normal);
+        let angle = angle.min(180. - angle);
+        assert!(angle < 1., "point {p} is off by {angle} degrees");
+    }
+    assert!(shapes.iter().all(|s| s.curvature < 1e-4));
+}
+
+#[test]
+fn orientation_propagates_over_the_sphere() {
+    let (mut points, normals) = sphere(5000);
+    for p in points.ids() {
+        let [x, y, z] = normals[p as usize];
+        let sign = if p % 3 == 0 { -1. } else { 1. };
+        points.normals[p as usize] = Some([sign * x, sign * y, sign * z]);
+    }
+
+    orient_normals(&mut points, 10, None);
+    assert!(points.ids().all(|p| vecmath::vec3_dot(points.normal(p).unwrap(), normals[p as usize]) > 0.));
+
+    // Seen from the center, the sphere is the inside of a room.
+    orient_normals(&mut points, 10, Some([0., 0., 0.]));
+    assert!(points.ids().all(|p| vecmath::vec3_dot(points.normal(p).unwrap(), normals[p as usize]) < 0.));
+}
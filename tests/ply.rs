@@ -0,0 +1,196 @@
+use ball_pivoting_rs::io::ply::{read_mesh_from, read_points_from, write_mesh_to, write_points_to, Format};
+use ball_pivoting_rs::io::IoError;
+use ball_pivoting_rs::point::Points;
+use ball_pivoting_rs::{reconstruct, ReconstructionOptions};
+
+mod common;
+
+const HEADER: &str = "ply
+format {format} 1.0
+comment written by hand
+element vertex {count}
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+property double intensity
+end_header
+";
+
+/// Returns the header of two vertices with colors and intensity in the given encoding.
+fn header(format: &str, count: usize) -> Vec<u8> {
+    HEADER.replace("{format}", format).replace("{count}", &count.to_string()).into_bytes()
+}
+
+/// Returns `header` followed by the vertices of [`check_vertices`] encoded as binary, with the
+/// bytes of every value reversed if `reverse` is set.
+fn binary(format: &str, reverse: bool) -> Vec<u8> {
+    let mut buffer = header(format, 2);
+    let mut push = |mut bytes: Vec<u8>| {
+        if reverse {
+            bytes.reverse();
+        }
+        buffer.extend(bytes);
+    };
+
+    for (coords, color, intensity) in [([1., -2., 3.5], [255, 0, 10], 0.25), ([0., 0.5, -1.], [1, 2, 3], 100.)] {
+        for c in coords {
+            push(f32::to_le_bytes(c).to_vec());
+        }
+        for c in color {
+            push(vec![c]);
+        }
+        push(f64::to_le_bytes(intensity).to_vec());
+    }
+
+    buffer
+}
+
+fn check_vertices(points: &Points) {
+    assert_eq!(points.len(), 2);
+    assert_eq!((points.coords(0), points.coords(1)), ([1., -2., 3.5], [0., 0.5, -1.]));
+    assert_eq!((points.color(0), points.color(1)), (Some([255, 0, 10]), Some([1, 2, 3])));
+    assert_eq!((points.intensity(0), points.intensity(1)), (Some(0.25), Some(100.)));
+    assert_eq!(points.normal(0), None);
+}
+
+#[test]
+fn ascii_vertices_are_read() {
+    let mut buffer = header("ascii", 2);
+    buffer.extend(b"1 -2 3.5 255 0 10 0.25\n0 0.5\n-1 1 2 3 1e2\n");
+
+    check_vertices(&read_points_from(&buffer[..]).unwrap());
+}
+
+#[test]
+fn binary_vertices_are_read_in_both_byte_orders() {
+    check_vertices(&read_points_from(&binary("binary_little_endian", false)[..]).unwrap());
+    check_vertices(&read_points_from(&binary("binary_big_endian", true)[..]).unwrap());
+}
+
+#[test]
+fn normals_and_float_colors_are_read() {
+    let buffer = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\n\
+        property float nx\nproperty float ny\nproperty float nz\nproperty float red\nproperty float green\nproperty float blue\n\
+        end_header\n1 2 3 0 0 1 1 0.5 0\n";
+
+    let points = read_points_from(buffer.as_bytes()).unwrap();
+
+    assert_eq!(points.normal(0), Some([0., 0., 1.]));
+    assert_eq!(points.color(0), Some([255, 128, 0]));
+    assert_eq!(points.intensity(0), None);
+}
+
+#[test]
+fn written_points_are_read_back() {
+    let mut points = Points::new();
+    for i in 0..10 {
+        let id = points.push(i as f32, -0.5 * i as f32, 1e-3, Some([0., 1., 0.]));
+        points.set_color(id, [i, 2 * i, 3 * i]);
+        points.set_intensity(id, i as f32 / 7.);
+    }
+
+    for format in [Format::Ascii, Format::BinaryLittleEndian, Format::BinaryBigEndian] {
+        let mut buffer = vec![];
+        write_points_to(&mut buffer, &points, format).unwrap();
+
+        assert_eq!(read_points_from(&buffer[..]).unwrap(), points, "{format:?}");
+    }
+}
+
+#[test]
+fn missing_coordinates_are_reported() {
+    let buffer = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nend_header\n1 2\n";
+
+    match read_points_from(buffer.as_bytes()) {
+        Err(IoError::MissingProperty { element, property }) => assert_eq!((&element[..], &property[..]), ("vertex", "z")),
+        result => panic!("{result:?}"),
+    }
+}
+
+#[test]
+fn truncated_files_are_reported() {
+    let mut buffer = header("ascii", 3);
+    buffer.extend(b"1 -2 3.5 255 0 10 0.25\n0 0.5 -1 1 2 3 1e2\n0 0\n");
+
+    assert!(matches!(read_points_from(&buffer[..]), Err(IoError::Truncated { expected: 3, read: 2, .. })));
+
+    let mut buffer = binary("binary_little_endian", false);
+    buffer.truncate(buffer.len() - 1);
+
+    assert!(matches!(read_points_from(&buffer[..]), Err(IoError::Truncated { expected: 2, read: 1, .. })));
+}
+
+#[test]
+fn huge_declared_counts_are_not_allocated() {
+    let mut buffer = header("binary_little_endian", 100_000_000_000);
+    buffer.extend(&binary("binary_little_endian", false)[header("binary_little_endian", 2).len()..]);
+
+    assert!(matches!(
+        read_points_from(&buffer[..]),
+        Err(IoError::Truncated { expected: 100_000_000_000, read: 2, .. })
+    ));
+}
+
+#[test]
+fn polygons_are_split_into_fans() {
+    let buffer = "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+        element face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n1 1 0\n0 1 0\n4 0 1 2 3\n";
+
+    let mesh = read_mesh_from(buffer.as_bytes()).unwrap();
+
+    assert_eq!(mesh.triangles(), &[[0, 1, 2], [0, 2, 3]]);
+    assert_eq!(mesh.normals, vec![[0.; 3]; 4]);
+}
+
+#[test]
+fn out_of_range_corners_are_reported() {
+    for (corner, index) in [("4", 4), ("-1", -1)] {
+        let buffer = format!(
+            "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+            element face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n1 1 0\n0 1 0\n3 0 1 {corner}\n"
+        );
+
+        assert!(matches!(
+            read_mesh_from(buffer.as_bytes()),
+            Err(IoError::InvalidIndex { index: i, num_vertices: 4 }) if i == index
+        ));
+    }
+}
+
+#[test]
+fn written_meshes_are_read_back() {
+    // A point far from the sphere is left out of the mesh, and so out of the file.
+    let (mut points, mut normals) = common::sphere(500);
+    points.push([100., 0., 0.]);
+    normals.push([1., 0., 0.]);
+
+    let mut mesh = reconstruct(&points, &normals, &ReconstructionOptions { radii: vec![2.], ..Default::default() }).unwrap();
+    mesh.colors = Some((0..points.len()).map(|i| [i as u8, 0, 255]).collect());
+
+    let mut used = mesh.triangles().iter().flatten().copied().collect::<Vec<_>>();
+    used.sort_unstable();
+    used.dedup();
+    assert!(!used.contains(&500));
+    let new_id = |p: u32| used.binary_search(&p).unwrap() as u32;
+
+    for format in [Format::Ascii, Format::BinaryLittleEndian, Format::BinaryBigEndian] {
+        let mut buffer = vec![];
+        write_mesh_to(&mut buffer, &mesh, format).unwrap();
+        let read = read_mesh_from(&buffer[..]).unwrap();
+
+        assert_eq!(read.vertices, used.iter().map(|&p| points[p as usize]).collect::<Vec<_>>(), "{format:?}");
+        assert_eq!(read.normals, used.iter().map(|&p| normals[p as usize]).collect::<Vec<_>>(), "{format:?}");
+        assert_eq!(read.colors, Some(used.iter().map(|&p| [p as u8, 0, 255]).collect()), "{format:?}");
+        assert_eq!(read.triangles(), mesh.triangles().iter().map(|t| t.map(new_id)).collect::<Vec<_>>(), "{format:?}");
+
+        // Faces are wound counter-clockwise around the vertex normals.
+        for &[a, b, c] in read.triangles() {
+            let [pa, pb, pc] = [a, b, c].map(|p| read.vertices[p as usize]);
+            let normal = vecmath::vec3_cross(vecmath::vec3_sub(pb, pa), vecmath::vec3_sub(pc, pa));
+            assert!(vecmath::vec3_dot(normal, read.normals[a as usize]) > 0., "{format:?}");
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use ball_pivoting_rs::point::Points;
+use ball_pivoting_rs::radius::estimate_spacing;
+
+mod common;
+
+/// A 10 by 10 grid of spacing 0.5 in the plane `z = 0`, every point repeated `copies` times.
+fn grid(copies: usize) -> Points {
+    let coords = (0..100)
+        .flat_map(|i| std::iter::repeat_n([(i % 10) as f32 * 0.5, (i / 10) as f32 * 0.5, 0.], copies))
+        .collect::<Vec<_>>();
+
+    common::points(&coords, None)
+}
+
+#[test]
+fn grid_spacing_is_measured() {
+    let spacing = estimate_spacing(&grid(1), 1000).unwrap();
+
+    assert_eq!(spacing.num_samples, 100);
+    assert_eq!((spacing.min, spacing.max, spacing.mean, spacing.median), (0.5, 0.5, 0.5, 0.5));
+    assert_eq!(spacing.suggested_radii(), vec![0.5, 1., 2.]);
+}
+
+#[test]
+fn samples_do_not_exceed_the_requested_number() {
+    for (num_samples, expected) in [(30, 25), (50, 50), (99, 50), (100, 100), (1, 1), (0, 1)] {
+        assert_eq!(estimate_spacing(&grid(1), num_samples).unwrap().num_samples, expected, "{num_samples} samples");
+    }
+}
+
+#[test]
+fn duplicates_are_not_the_nearest_neighbor() {
+    // Three duplicates of a point still leave one neighbor at the grid spacing.
+    let spacing = estimate_spacing(&grid(4), 1000).unwrap();
+    assert_eq!((spacing.num_samples, spacing.min, spacing.max), (400, 0.5, 0.5));
+
+    // With more duplicates, the points are not sampled.
+    assert_eq!(estimate_spacing(&grid(5), 1000), None);
+}
+
+#[test]
+fn percentiles_index_the_sorted_distances() {
+    // Gaps of 1, 2, 3 and 4, the first two points are each other's nearest neighbor.
+    let points = common::points(&[[0., 0., 0.], [1., 0., 0.], [3., 0., 0.], [6., 0., 0.], [10., 0., 0.]], None);
+    let spacing = estimate_spacing(&points, 5).unwrap();
+
+    assert_eq!(spacing.distances, vec![1., 1., 2., 3., 4.]);
+    assert_eq!(spacing.median, 2.);
+    for (q, expected) in [(0., 1.), (0.5, 2.), (0.75, 3.), (1., 4.), (-1., 1.), (2., 4.)] {
+        assert_eq!(spacing.percentile(q), expected, "{q}");
+    }
+}
@@ -0,0 +1,216 @@
+use ball_pivoting_rs::{reconstruct, reconstruct_with_progress, validate, BpaError, Mesh, ReconstructionOptions};
+
+mod common;
+
+/// Points on a noisy `size` by `size` grid of the plane `z = 0`, with normals along `z`.
+fn slab(size: usize, noise: f32) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+    let mut seed = 12345u32;
+    let mut random = || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        noise * ((seed >> 8) as f32 / (1 << 24) as f32 - 0.5)
+    };
+
+    (0..size * size)
+        .map(|i| ([(i / size) as f32 + random(), (i % size) as f32 + random(), random()], [0., 0., 1.]))
+        .unzip()
+}
+
+#[test]
+fn dense_sphere_is_closed() {
+    let (points, normals) = common::sphere(50000);
+    let options = ReconstructionOptions { radii: vec![0.3], num_workers: 1, ..Default::default() };
+
+    let mesh = reconstruct(&points, &normals, &options).unwrap();
+    let report = validate(&mesh);
+
+    assert!(report.is_closed(), "{report:?}");
+    assert_eq!(report.genus, Some(0));
+}
+
+#[test]
+fn noisy_slab_is_manifold() {
+    let (points, normals) = slab(30, 1.);
+
+    for radii in [vec![], vec![1.], vec![0.8, 1.6]] {
+        let options = ReconstructionOptions { radii: radii.clone(), ..Default::default() };
+
+        let mesh = reconstruct(&points, &normals, &options).unwrap();
+        let report = validate(&mesh);
+
+        assert!(report.is_manifold() && report.is_valid(), "{radii:?}: {report:?}");
+        assert!(mesh.triangles().len() > 1000, "{radii:?}: {} triangles", mesh.triangles().len());
+    }
+}
+
+#[test]
+fn fronts_meet_without_pinching_the_mesh() {
+    // Fronts of the small ball stop on both sides of narrow gaps, the large one joins them.
+    // Debug builds also assert that no vertex is pinched before the mesh is returned.
+    let (points, normals) = slab(60, 0.5);
+    let options = ReconstructionOptions { radii: vec![0.8, 1.6], ..Default::default() };
+
+    let mesh = reconstruct(&points, &normals, &options).unwrap();
+    let report = validate(&mesh);
+
+    assert!(report.is_manifold() && report.is_valid(), "{report:?}");
+    // Out of the 2 * 59 * 59 triangles of the grid.
+    assert!(mesh.triangles().len() > 6900, "{} triangles", mesh.triangles().len());
+}
+
+#[test]
+fn progress_reports_the_radius_of_each_pass() {
+    let (points, normals) = common::sphere(500);
+    let options = ReconstructionOptions { radii: vec![1.5, 2.5], ..Default::default() };
+
+    let mut reports = vec![];
+    reconstruct_with_progress(&points, &normals, &options, |progress| reports.push((progress.pass, progress.radius))).unwrap();
+
+    assert_eq!(reports.first(), Some(&(0, 1.5)));
+    assert!(reports.iter().all(|&(pass, radius)| radius == options.radii[pass]));
+    assert!(reports.contains(&(1, 2.5)));
+}
+
+/// Returns the triangles of `mesh` starting at their smallest corner, in increasing order.
+fn sorted_triangles(mesh: &Mesh) -> Vec<[u32; 3]> {
+    let mut triangles = mesh
+        .triangles()
+        .iter()
+        .map(|&[a, b, c]| match a.min(b).min(c) {
+            min if min == a => [a, b, c],
+            min if min == b => [b, c, a],
+            _ => [c, a, b],
+        })
+        .collect::<Vec<_>>();
+    triangles.sort_unstable();
+
+    triangles
+}
+
+#[test]
+fn workers_build_the_same_surface() {
+    // The large ball of the slab fills the holes left by the small one, across the seams.
+    let sphere = common::sphere(5000);
+    let slab = slab(30, 0.4);
+
+    for ((points, normals), radii) in [(&sphere, vec![1.]), (&slab, vec![0.8, 1.6])] {
+        let mesh = |num_workers| {
+            let options = ReconstructionOptions { radii: radii.clone(), num_workers, ..Default::default() };
+            reconstruct(points, normals, &options).unwrap()
+        };
+
+        let serial = mesh(1);
+        assert!(validate(&serial).is_manifold());
+        for num_workers in [2, 4] {
+            assert_eq!(sorted_triangles(&serial), sorted_triangles(&mesh(num_workers)), "{radii:?}, {num_workers} workers");
+        }
+    }
+
+    let (points, normals) = sphere;
+    let options = ReconstructionOptions { radii: vec![1.], num_workers: 4, ..Default::default() };
+    assert!(validate(&reconstruct(&points, &normals, &options).unwrap()).is_closed());
+}
+
+#[test]
+fn seed_limit_holds_over_all_workers() {
+    // Two spheres far apart, a single seed only meshes the first one.
+    let (mut points, mut normals) = common::sphere(5000);
+    points.extend(points.clone().iter().map(|&[x, y, z]| [x + 30., y, z]));
+    normals.extend_from_within(..);
+
+    for num_workers in [1, 2, 4] {
+        let options = ReconstructionOptions { radii: vec![1.], limit_iterations: Some(1), num_workers, ..Default::default() };
+        let mesh = reconstruct(&points, &normals, &options).unwrap();
+
+        let report = validate(&mesh);
+        assert_eq!(report.num_components, 1, "{num_workers} workers");
+        assert!(report.is_closed() && mesh.triangles().iter().flatten().all(|&p| p < 5000), "{num_workers} workers");
+    }
+}
+
+#[test]
+fn larger_radius_closes_the_holes_of_the_smaller_one() {
+    // Every fourth point is kept on the cap beyond `x = 8`, too sparse for the small ball.
+    let (coords, directions) = common::sphere(5000);
+    let (points, normals): (Vec<_>, Vec<_>) = (0..coords.len())
+        .filter(|&i| coords[i][0] < 8. || i % 4 == 0)
+        .map(|i| (coords[i], directions[i]))
+        .unzip();
+    let mesh = |radii: Vec<f32>| reconstruct(&points, &normals, &ReconstructionOptions { radii, ..Default::default() }).unwrap();
+
+    let small = mesh(vec![0.6]);
+    assert_eq!(validate(&small).boundary_loops.len(), 1);
+
+    let both = mesh(vec![0.6, 1.2]);
+    assert!(validate(&both).is_closed());
+    assert_eq!(both.triangle_radii.len(), both.triangles().len());
+    assert_eq!(both.triangle_radii.iter().filter(|&&r| r == 0.6).count(), small.triangles().len());
+
+    let closing = (0..both.triangles().len()).filter(|&f| both.triangle_radii[f] == 1.2).collect::<Vec<_>>();
+    assert!(!closing.is_empty());
+    assert!(closing.iter().all(|&f| both.triangles()[f].iter().any(|&p| points[p as usize][0] > 7.5)));
+}
+
+#[test]
+fn bad_input_is_reported() {
+    let up = [[0., 0., 1.]; 4];
+    let square = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [0., 1., 0.]];
+    let options = ReconstructionOptions { radii: vec![1.], ..Default::default() };
+
+    assert_eq!(reconstruct(&square[..2], &up[..2], &options).unwrap_err(), BpaError::TooFewPoints { found: 2, required: 3 });
+    assert_eq!(reconstruct(&square, &up[..3], &options).unwrap_err(), BpaError::LengthMismatch { points: 4, normals: 3 });
+
+    let mut with_nan = square;
+    with_nan[2][1] = f32::NAN;
+    assert_eq!(reconstruct(&with_nan, &up, &options).unwrap_err(), BpaError::NonFiniteCoordinate { point_id: 2 });
+
+    for radius in [-1., 0., f32::NAN, f32::INFINITY] {
+        let options = ReconstructionOptions { radii: vec![1., radius], ..Default::default() };
+        let error = reconstruct(&square, &up, &options).unwrap_err();
+        assert!(matches!(error, BpaError::InvalidRadius(r) if r.to_bits() == radius.to_bits()), "{radius}: {error:?}");
+    }
+
+    // Whether the radius is given or estimated from the spacing.
+    for radii in [vec![1.], vec![]] {
+        let options = ReconstructionOptions { radii, ..Default::default() };
+        assert_eq!(reconstruct(&[[1., 2., 3.]; 4], &up, &options).unwrap_err(), BpaError::DegenerateInput);
+    }
+}
+
+#[test]
+fn collinear_points_have_no_seed() {
+    let points = (0..10).map(|i| [i as f32 * 0.5, 0., 0.]).collect::<Vec<_>>();
+    let options = ReconstructionOptions { radii: vec![1.], ..Default::default() };
+
+    assert_eq!(reconstruct(&points, &[[0., 0., 1.]; 10], &options).unwrap_err(), BpaError::NoSeedFound);
+}
+
+#[test]
+fn corners_with_a_flipped_normal_build_no_triangle() {
+    let points = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+
+    for flipped in 0..3 {
+        let mut normals = [[0., 0., 1.]; 3];
+        normals[flipped] = [0., 0., -1.];
+
+        let options = ReconstructionOptions { radii: vec![1.], ..Default::default() };
+        assert_eq!(reconstruct(&points, &normals, &options).unwrap_err(), BpaError::NoSeedFound, "{flipped}");
+    }
+}
+
+#[test]
+fn pivots_do_not_cross_a_thin_sheet() {
+    // Both sides of a sheet thinner than the ball, the top with normals up, the bottom down.
+    let (points, normals): (Vec<_>, Vec<_>) = (0..200)
+        .map(|i| {
+            let (x, y, side) = ((i % 10) as f32 * 0.5, (i / 10 % 10) as f32 * 0.5, if i < 100 { 1. } else { -1. });
+            ([x, y, 0.1 * side], [0., 0., side])
+        })
+        .unzip();
+    let options = ReconstructionOptions { radii: vec![0.5], ..Default::default() };
+
+    let mesh = reconstruct(&points, &normals, &options).unwrap();
+
+    let is_top = |triangle: &[u32; 3]| triangle.map(|p| p < 100);
+    assert!(mesh.triangles().iter().all(|t| is_top(t) == [true; 3] || is_top(t) == [false; 3]));
+    assert!(mesh.triangles().iter().any(|t| t[0] < 100) && mesh.triangles().iter().any(|t| t[0] >= 100));
+}
@@ -0,0 +1,88 @@
+use ball_pivoting_rs::io::text::{read_points_from, Columns, TextOptions};
+use ball_pivoting_rs::io::IoError;
+use ball_pivoting_rs::point::Points;
+
+fn coords(points: &Points) -> Vec<[f32; 3]> {
+    points.ids().map(|p| points.coords(p)).collect()
+}
+
+#[test]
+fn whitespace_comments_and_blank_lines() {
+    let text = "# scan 12\n1 2 3\n\n  \t4\t5   6 extra\n  # indented comment\n-7 8e-1 9\n";
+
+    let points = read_points_from(text.as_bytes(), &TextOptions::default()).unwrap();
+
+    assert_eq!(coords(&points), vec![[1., 2., 3.], [4., 5., 6.], [-7., 0.8, 9.]]);
+    assert_eq!((points.normal(0), points.colors.as_ref(), points.intensities.as_ref()), (None, None, None));
+}
+
+#[test]
+fn comments_can_be_disabled_or_changed() {
+    let text = "// header\n1 2 3\n";
+
+    let options = TextOptions { comment: Some("//".to_string()), ..TextOptions::default() };
+    assert_eq!(read_points_from(text.as_bytes(), &options).unwrap().len(), 1);
+
+    let options = TextOptions { comment: None, ..TextOptions::default() };
+    assert!(matches!(read_points_from(text.as_bytes(), &options), Err(IoError::InvalidValue { line: 1, .. })));
+}
+
+#[test]
+fn columns_are_mapped() {
+    let text = "9; 0; 1; 0; 3; 2; 1; 255; 128.4; 300\n";
+    let options = TextOptions {
+        columns: Columns { x: 5, y: 4, z: 6, normal: Some([1, 2, 3]), color: Some([7, 8, 9]), intensity: Some(0) },
+        delimiter: Some(';'),
+        ..TextOptions::default()
+    };
+
+    let points = read_points_from(text.as_bytes(), &options).unwrap();
+
+    assert_eq!(coords(&points), vec![[2., 3., 1.]]);
+    assert_eq!(points.normal(0), Some([0., 1., 0.]));
+    assert_eq!(points.color(0), Some([255, 128, 255]));
+    assert_eq!(points.intensity(0), Some(9.));
+}
+
+#[test]
+fn csv_skips_its_header() {
+    let text = "x,y,z\n1, 2 ,3\n4,5,6\n";
+
+    let points = read_points_from(text.as_bytes(), &TextOptions::csv()).unwrap();
+
+    assert_eq!(coords(&points), vec![[1., 2., 3.], [4., 5., 6.]]);
+}
+
+#[test]
+fn pts_has_a_count_then_intensity_and_colors() {
+    let text = "2\n1 2 3 -1200 10 20 30\n4 5 6 35 40 50 60\n";
+
+    let points = read_points_from(text.as_bytes(), &TextOptions::pts()).unwrap();
+
+    assert_eq!(coords(&points), vec![[1., 2., 3.], [4., 5., 6.]]);
+    assert_eq!(points.intensities, Some(vec![-1200., 35.]));
+    assert_eq!(points.colors, Some(vec![[10, 20, 30], [40, 50, 60]]));
+}
+
+#[test]
+fn xyz_normals_reads_six_columns() {
+    let points = read_points_from("1 2 3 0 0 -1\n".as_bytes(), &TextOptions::xyz_normals()).unwrap();
+
+    assert_eq!(points.normal(0), Some([0., 0., -1.]));
+}
+
+#[test]
+fn short_lines_and_bad_values_are_reported() {
+    // Line numbers count the skipped, blank and comment lines.
+    let text = "x,y,z\n1,2,3\n\n# two columns\n4,5\n";
+    match read_points_from(text.as_bytes(), &TextOptions::csv()) {
+        Err(IoError::MissingColumn { line, column }) => assert_eq!((line, column), (5, 2)),
+        result => panic!("{result:?}"),
+    }
+
+    let text = "1 2 3\n4 five 6\n";
+    match read_points_from(text.as_bytes(), &TextOptions::default()) {
+        Err(IoError::InvalidValue { line, value }) => assert_eq!((line, &value[..]), (2, "five")),
+        result => panic!("{result:?}"),
+    }
+}
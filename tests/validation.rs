@@ -0,0 +1,53 @@
+use ball_pivoting_rs::{validate, HalfEdgeMesh, Mesh};
+
+fn mesh(vertices: &[[f32; 3]], triangles: &[[u32; 3]]) -> Mesh {
+    Mesh {
+        vertices: vertices.to_vec(),
+        normals: vec![[0.; 3]; vertices.len()],
+        topology: HalfEdgeMesh::from_faces(triangles.iter().copied()),
+        ..Mesh::default()
+    }
+}
+
+const TETRAHEDRON: [[f32; 3]; 4] = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+
+#[test]
+fn tetrahedron_is_a_closed_sphere() {
+    let report = validate(&mesh(&TETRAHEDRON, &[[0, 2, 1], [0, 1, 3], [1, 2, 3], [0, 3, 2]]));
+
+    assert!(report.is_closed());
+    assert_eq!((report.num_vertices, report.num_edges, report.num_faces), (4, 6, 4));
+    assert_eq!(report.euler_characteristic, 2);
+    assert_eq!(report.genus, Some(0));
+    assert_eq!(report.num_components, 1);
+}
+
+#[test]
+fn open_and_flipped_faces_are_reported() {
+    let report = validate(&mesh(&TETRAHEDRON, &[[0, 2, 1], [0, 2, 3]]));
+
+    assert!(report.is_manifold() && !report.is_valid());
+    assert_eq!(report.inconsistent_edges.len(), 1);
+    assert_eq!(report.boundary_loops.len(), 1);
+    assert_eq!(report.genus, None);
+}
+
+#[test]
+fn bow_tie_has_a_non_manifold_vertex() {
+    let vertices = [[0., 0., 0.], [1., 0., 0.], [1., 1., 0.], [-1., 0., 0.], [-1., -1., 0.]];
+    let report = validate(&mesh(&vertices, &[[0, 1, 2], [0, 3, 4]]));
+
+    assert_eq!(report.non_manifold_vertices, vec![0]);
+    assert_eq!(report.num_components, 1);
+    assert!(!report.is_manifold());
+}
+
+#[test]
+fn duplicate_degenerate_and_non_manifold_edges_are_reported() {
+    let vertices = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [0., -1., 0.], [0., 0., 1.], [2., 0., 0.]];
+    let report = validate(&mesh(&vertices, &[[0, 1, 2], [1, 0, 3], [0, 1, 4], [2, 1, 0], [0, 1, 5]]));
+
+    assert_eq!(report.non_manifold_edges, vec![[0, 1]]);
+    assert_eq!(report.duplicate_faces, vec![3]);
+    assert_eq!(report.degenerate_faces, vec![4]);
+}